@@ -0,0 +1,87 @@
+use std::f32::consts::TAU;
+
+use crate::auto::{Actions, Strategy};
+
+const SPEED: f32 = 0.003;
+const BASE_RADIUS: f64 = 6.0;
+const WIND: f32 = 0.3;
+
+enum Intention {
+    ChooseColor,
+    Sweep { left_to_right: bool },
+}
+
+/// Sweeps across the width, spawning in waves so the wind piles the sand into dunes.
+pub struct Dunes {
+    intention: Intention,
+    next_l_to_r: bool,
+    /// Phase of the spawn rate wave, advanced every tick.
+    phase: f32,
+    /// Phase change per tick, a new one is rolled for every sweep.
+    frequency: f32,
+    spawning: bool,
+    wind: f32,
+}
+
+impl Dunes {
+    pub fn new() -> Self {
+        Self {
+            intention: Intention::ChooseColor,
+            next_l_to_r: true,
+            phase: 0.0,
+            frequency: 0.0,
+            spawning: false,
+            wind: if fastrand::bool() { WIND } else { -WIND },
+        }
+    }
+}
+
+impl Strategy for Dunes {
+    fn starting_pos(&self) -> (f32, f32) {
+        (0.0, 0.001)
+    }
+
+    fn should_spawn(&self) -> bool {
+        self.spawning
+    }
+
+    fn choose_new_color(&mut self) -> bool {
+        let Intention::ChooseColor = self.intention else {
+            return false;
+        };
+
+        self.intention = Intention::Sweep {
+            left_to_right: self.next_l_to_r,
+        };
+        self.next_l_to_r = !self.next_l_to_r;
+        self.frequency = TAU / fastrand::u32(60..240) as f32;
+        true
+    }
+
+    fn tick(&mut self, mouse_pos: &mut (f32, f32), _: &mut Actions) {
+        let Intention::Sweep { left_to_right } = self.intention else {
+            self.spawning = false;
+            return;
+        };
+
+        mouse_pos.0 += SPEED * (if left_to_right { 1.0 } else { -1.0 });
+        self.phase = (self.phase + self.frequency) % TAU;
+
+        let rate = 0.5 + 0.5 * self.phase.sin();
+        self.spawning = fastrand::f32() < rate * rate;
+
+        if mouse_pos.0 >= 1.0 || mouse_pos.0 <= 0.0 {
+            mouse_pos.0 = mouse_pos.0.clamp(0.0, 1.0);
+            self.intention = Intention::ChooseColor;
+        }
+    }
+
+    fn radius(&self, _: f32) -> Option<f64> {
+        let swell = (self.phase * 3.0).sin() as f64;
+        Some(BASE_RADIUS * (1.0 + 0.5 * swell))
+    }
+
+    fn wind(&self) -> f32 {
+        self.wind
+    }
+}
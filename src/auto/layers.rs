@@ -1,11 +1,7 @@
-use crate::{
-    auto::{utils::is_nice_color, Strategy},
-    gradient::Gradient,
-};
+use crate::auto::{Actions, Strategy};
 
-const MAX_TRIES: u8 = 5;
 enum Intention {
-    ChooseColor { tries: u8 },
+    ChooseColor,
     DrawLayer { left_to_right: bool },
 }
 
@@ -16,7 +12,7 @@ pub struct Layers {
 impl Layers {
     pub(crate) fn new() -> Self {
         Self {
-            intention: Intention::ChooseColor { tries: 0 },
+            intention: Intention::ChooseColor,
             next_l_to_r: true,
         }
     }
@@ -31,24 +27,19 @@ impl Strategy for Layers {
         matches!(self.intention, Intention::DrawLayer { .. })
     }
 
-    fn choose_new_color(&mut self, gradient: &Gradient) -> bool {
-        let Intention::ChooseColor { tries } = self.intention else {
+    fn choose_new_color(&mut self) -> bool {
+        let Intention::ChooseColor = self.intention else {
             return false;
         };
 
-        if tries != 0 && (tries > MAX_TRIES || is_nice_color(gradient.peek_color())) {
-            self.intention = Intention::DrawLayer {
-                left_to_right: self.next_l_to_r,
-            };
-            self.next_l_to_r = !self.next_l_to_r;
-            false
-        } else {
-            self.intention = Intention::ChooseColor { tries: tries + 1 };
-            true
-        }
+        self.intention = Intention::DrawLayer {
+            left_to_right: self.next_l_to_r,
+        };
+        self.next_l_to_r = !self.next_l_to_r;
+        true
     }
 
-    fn tick(&mut self, mouse_pos: &mut (f32, f32)) {
+    fn tick(&mut self, mouse_pos: &mut (f32, f32), _: &mut Actions) {
         let Intention::DrawLayer { left_to_right } = self.intention else {
             return;
         };
@@ -56,7 +47,7 @@ impl Strategy for Layers {
         mouse_pos.0 += change;
 
         if mouse_pos.0 >= 1.0 || mouse_pos.0 <= 0.0 {
-            self.intention = Intention::ChooseColor { tries: 0 };
+            self.intention = Intention::ChooseColor;
         }
     }
 }
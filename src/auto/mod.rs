@@ -1,59 +1,325 @@
-use crate::gradient::Gradient;
+use std::{
+    collections::VecDeque,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
+use fastrand::Rng;
+
+use crate::{brush, double::Event, gradient::Gradient, material::SAND, palette::Gradients};
+use skyline::Profile;
+use utils::ColorPolicy;
+
+mod dunes;
 mod layers;
 mod mountains;
+mod script;
+pub mod skyline;
 pub mod utils;
 
+/// One-off requests a strategy can make during [`Strategy::tick`].
+#[derive(Default)]
+pub struct Actions {
+    /// Normalized positions to spawn at this tick, besides the cursor.
+    pub spawns: Vec<(f32, f32)>,
+    /// Gradient to draw with from now on, a solid color is a gradient between itself.
+    pub gradient: Option<Gradient>,
+    pub clear: bool,
+}
+
 trait Strategy {
     fn starting_pos(&self) -> (f32, f32);
     fn should_spawn(&self) -> bool;
-    fn choose_new_color(&mut self, gradient: &Gradient) -> bool;
-    fn tick(&mut self, mouse_pos: &mut (f32, f32));
+    /// Whether the strategy wants a new gradient, which is picked following
+    /// the [`ColorPolicy`].
+    fn choose_new_color(&mut self) -> bool;
+    fn tick(&mut self, mouse_pos: &mut (f32, f32), actions: &mut Actions);
+
+    /// Called before every tick with the resting sand height per column, as
+    /// fractions of the grid height. Empty unless [`Strategy::wants_surface`].
+    fn observe(&mut self, _surface: &[f32]) {}
+
+    /// Whether [`Strategy::observe`] needs the surface, measuring it costs a
+    /// scan of the grid whenever it changes.
+    fn wants_surface(&self) -> bool {
+        false
+    }
+
+    /// Brush radius to draw with instead of the user's, `width` is how many
+    /// cells wide the strategy's stretch of the grid is.
+    fn radius(&self, _width: f32) -> Option<f64> {
+        None
+    }
+
+    /// Sideways push on falling grains, see [`crate::grid::Grid::set_wind`].
+    fn wind(&self) -> f32 {
+        0.0
+    }
 }
 
-pub struct Auto {
-    pub enabled: bool,
+#[derive(Clone)]
+pub struct Settings {
+    /// Switch to the next strategy after this long, if set.
+    pub playlist: Option<Duration>,
+    pub script: Option<PathBuf>,
+    /// How many cursors draw at once, each with its own strategy instance.
+    pub emitters: usize,
+    pub gradients: Gradients,
+    pub colors: ColorPolicy,
+    /// Skyline the skyline strategy builds towards.
+    pub profile: Profile,
+}
+
+/// Candidates rolled per new gradient, the one with the lowest penalty is used.
+const CANDIDATES: usize = 16;
+/// Smallest and largest part of the radius every emitter but the first draws with.
+const RADIUS_SCALES: (f64, f64) = (0.5, 1.5);
+
+/// Builds a strategy, or `None` if the settings don't allow it.
+type Constructor = fn(&Settings) -> Option<Box<dyn Strategy>>;
+
+/// Every selectable strategy, in the order they are cycled through.
+static STRATEGIES: &[(&str, Constructor)] = &[
+    ("mountains", |_| Some(Box::new(mountains::Mountains::new()))),
+    ("layers", |_| Some(Box::new(layers::Layers::new()))),
+    ("dunes", |_| Some(Box::new(dunes::Dunes::new()))),
+    ("script", |settings| {
+        let path = settings.script.as_deref()?;
+        Some(Box::new(script::Script::new(path)))
+    }),
+    ("skyline", |settings| {
+        Some(Box::new(skyline::Skyline::new(&settings.profile)))
+    }),
+];
+
+pub fn find_strategy(name: &str) -> Option<usize> {
+    STRATEGIES.iter().position(|(n, _)| *n == name)
+}
+
+pub fn strategy_names() -> impl Iterator<Item = &'static str> {
+    STRATEGIES.iter().map(|(name, _)| *name)
+}
+
+/// A virtual cursor driven by a strategy.
+struct Emitter {
     strategy: Box<dyn Strategy>,
     mouse_pos: (f32, f32),
+    gradient: Gradient,
+    /// Where the last stamp landed, for interpolating strokes.
+    last_stamp: Option<(u16, u16)>,
+    /// Left and right edge of the stretch of the grid the emitter draws in,
+    /// normalized. Its strategy sees the stretch as the whole width.
+    lane: (f32, f32),
+    /// Part of the radius the emitter draws with, so emitters differ in size.
+    radius_scale: f64,
 }
 
-impl Auto {
-    pub fn new() -> Self {
-        // let strategy = Box::new(layers::Layers::new());
-        let strategy = Box::new(mountains::Mountains::new());
-        let mouse_pos = strategy.starting_pos();
+impl Emitter {
+    /// Where normalized position `x`, `y` inside the lane is on the whole grid.
+    fn in_grid(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        (self.lane.0 + x * (self.lane.1 - self.lane.0), y)
+    }
+
+    /// The columns of `surface` inside the lane.
+    fn lane_surface<'a>(&self, surface: &'a [f32]) -> &'a [f32] {
+        let column = |x: f32| (x * surface.len() as f32).round() as usize;
+        &surface[column(self.lane.0)..column(self.lane.1)]
+    }
+}
+
+pub struct Auto {
+    pub enabled: bool,
+    index: usize,
+    emitters: Vec<Emitter>,
+    settings: Settings,
+    rng: Rng,
+    /// Ends of the most recently picked gradients, newest first.
+    history: VecDeque<(u32, u32)>,
+    started: Instant,
+}
 
-        Self {
+impl Auto {
+    /// Panics if strategy `index` is not available with `settings`.
+    pub fn new(index: usize, settings: Settings) -> Self {
+        let mut auto = Self {
             enabled: false,
-            strategy,
-            mouse_pos,
-        }
+            index,
+            emitters: vec![],
+            settings,
+            rng: Rng::new(),
+            history: VecDeque::new(),
+            started: Instant::now(),
+        };
+        auto.emitters = auto.build(index).expect("strategy is available");
+        auto
+    }
+
+    fn build(&mut self, index: usize) -> Option<Vec<Emitter>> {
+        let count = self.settings.emitters;
+        (0..count)
+            .map(|i| {
+                let strategy = (STRATEGIES[index].1)(&self.settings)?;
+                let radius_scale = if i == 0 {
+                    1.0
+                } else {
+                    let (smallest, largest) = RADIUS_SCALES;
+                    smallest + self.rng.f64() * (largest - smallest)
+                };
+                Some(Emitter {
+                    mouse_pos: strategy.starting_pos(),
+                    strategy,
+                    gradient: self.pick_gradient(),
+                    last_stamp: None,
+                    lane: (i as f32 / count as f32, (i + 1) as f32 / count as f32),
+                    radius_scale,
+                })
+            })
+            .collect()
+    }
+
+    fn pick_gradient(&mut self) -> Gradient {
+        let policy = &self.settings.colors;
+        let gradient = (0..CANDIDATES)
+            .map(|_| self.settings.gradients.next(&mut self.rng))
+            .min_by(|a, b| {
+                let a = policy.penalty(a, &self.history);
+                a.total_cmp(&policy.penalty(b, &self.history))
+            })
+            .unwrap();
+
+        self.history.push_front(gradient.ends());
+        self.history.truncate(policy.history);
+        gradient
+    }
+
+    fn restart(&mut self) {
+        self.emitters = self.build(self.index).expect("strategy is available");
+        self.started = Instant::now();
     }
 
     pub fn flip(&mut self) {
         if !self.enabled {
-            *self = Auto::new();
             self.enabled = true;
+            self.restart();
         } else {
             self.enabled = false;
         }
     }
 
-    pub fn mouse_pos(&self) -> (f32, f32) {
-        self.mouse_pos
+    pub fn next_strategy(&mut self) {
+        for _ in 0..STRATEGIES.len() {
+            self.index = (self.index + 1) % STRATEGIES.len();
+            if let Some(emitters) = self.build(self.index) {
+                self.emitters = emitters;
+                self.started = Instant::now();
+                return;
+            }
+        }
+    }
+
+    pub fn strategy_name(&self) -> &'static str {
+        STRATEGIES[self.index].0
+    }
+
+    /// Gradient of the first emitter, for showing what auto mode is drawing with.
+    pub fn gradient(&self) -> Option<&Gradient> {
+        self.emitters
+            .first()
+            .filter(|_| self.enabled)
+            .map(|e| &e.gradient)
     }
 
-    pub fn should_spawn(&self) -> bool {
-        self.enabled && self.strategy.should_spawn()
+    /// Whether any emitter needs the surface passed to [`Auto::tick`].
+    pub fn wants_surface(&self) -> bool {
+        self.enabled && self.emitters.iter().any(|e| e.strategy.wants_surface())
     }
 
-    pub fn choose_new_color(&mut self, gradient: &Gradient) -> bool {
-        self.enabled && self.strategy.choose_new_color(gradient)
+    /// Average wind of all emitters, the grid only has one.
+    pub fn wind(&self) -> f32 {
+        if !self.enabled {
+            return 0.0;
+        }
+
+        let total: f32 = self.emitters.iter().map(|e| e.strategy.wind()).sum();
+        total / self.emitters.len() as f32
     }
 
-    pub fn tick(&mut self) {
-        if self.enabled {
-            self.strategy.tick(&mut self.mouse_pos);
+    /// Advances every emitter, returning the events they produced.
+    ///
+    /// `size` is the grid size, `radius` is used by strategies that don't
+    /// pick their own and `surface` is the latest [`crate::double::surface`].
+    pub fn tick(&mut self, size: (u16, u16), radius: f64, surface: &[f32]) -> Vec<Event> {
+        let mut events = vec![];
+        if !self.enabled {
+            return events;
+        }
+
+        if self
+            .settings
+            .playlist
+            .is_some_and(|d| self.started.elapsed() >= d)
+        {
+            self.next_strategy();
         }
+
+        let to_grid = |(x, y): (f32, f32)| {
+            (
+                (size.0 as f32 * x).round() as u16,
+                (size.1 as f32 * y).round() as u16,
+            )
+        };
+
+        for i in 0..self.emitters.len() {
+            let mut actions = Actions::default();
+            let emitter = &mut self.emitters[i];
+            emitter.strategy.observe(emitter.lane_surface(surface));
+            emitter.strategy.tick(&mut emitter.mouse_pos, &mut actions);
+
+            if let Some(gradient) = actions.gradient {
+                emitter.gradient = gradient;
+            }
+
+            if emitter.strategy.choose_new_color() {
+                let gradient = self.pick_gradient();
+                self.emitters[i].gradient = gradient;
+            }
+
+            let emitter = &mut self.emitters[i];
+            if actions.clear {
+                events.push(Event::Clear);
+            }
+
+            let lane_width = (emitter.lane.1 - emitter.lane.0) * size.0 as f32;
+            let radius =
+                emitter.strategy.radius(lane_width).unwrap_or(radius) * emitter.radius_scale;
+            let position = to_grid(emitter.in_grid(emitter.mouse_pos));
+            if emitter.strategy.should_spawn() {
+                let from = emitter.last_stamp.unwrap_or(position);
+                for position in brush::stroke(from, position, radius / 2.0) {
+                    let color = emitter.gradient.next_color();
+                    events.push(Event::Spawn {
+                        color,
+                        position,
+                        radius,
+                        material: SAND,
+                    });
+                }
+                emitter.last_stamp = Some(position);
+            } else {
+                emitter.last_stamp = None;
+            }
+
+            for position in actions.spawns {
+                let color = emitter.gradient.next_color();
+                events.push(Event::Spawn {
+                    color,
+                    position: to_grid(emitter.in_grid(position)),
+                    radius,
+                    material: SAND,
+                });
+            }
+        }
+
+        events
     }
 }
@@ -1,13 +1,11 @@
-use crate::{
-    auto::{utils::is_nice_color, Strategy},
-    gradient::Gradient,
-};
+use std::f32::consts::TAU;
 
-const MAX_TRIES: u8 = 5;
+use crate::auto::{Actions, Strategy};
+
+/// Brush radius for a mountain spanning the whole width, as a part of the width.
+const FULL_WIDTH_RADIUS: f32 = 0.75;
 enum Intention {
-    ChooseColor {
-        tries: u8,
-    },
+    ChooseColor,
     DrawMountain {
         x_pos: f32,
         ticks_ttl: u32,
@@ -18,16 +16,17 @@ enum Intention {
 
 pub struct Mountains {
     intention: Intention,
-    next_l_to_r: bool,
     started_drawing: bool,
+    /// Phase of the radius oscillation while drawing.
+    phase: f32,
 }
 
 impl Mountains {
     pub fn new() -> Self {
         Self {
-            intention: Intention::ChooseColor { tries: 0 },
-            next_l_to_r: true,
+            intention: Intention::ChooseColor,
             started_drawing: false,
+            phase: 0.0,
         }
     }
 }
@@ -41,33 +40,29 @@ impl Strategy for Mountains {
         matches!(self.intention, Intention::DrawMountain { .. }) && !self.started_drawing
     }
 
-    fn choose_new_color(&mut self, gradient: &Gradient) -> bool {
-        let Intention::ChooseColor { tries } = self.intention else {
+    fn choose_new_color(&mut self) -> bool {
+        let Intention::ChooseColor = self.intention else {
             return false;
         };
 
-        if tries != 0 && (tries > MAX_TRIES || is_nice_color(gradient.peek_color())) {
-            let x_pos = fastrand::f32();
-            let max_width = (1.0 - x_pos).abs().min(0.2);
-            let width = fastrand::f32() * max_width;
-            let ticks_ttl = fastrand::u32(200..1000);
-            let velocity = width * 4.0 / (ticks_ttl as f32);
+        let x_pos = fastrand::f32();
+        let max_width = (1.0 - x_pos).abs().min(0.2);
+        let width = fastrand::f32() * max_width;
+        let ticks_ttl = fastrand::u32(200..1000);
+        let velocity = fastrand::u32(2..8) as f32 * TAU / ticks_ttl as f32;
 
-            self.intention = Intention::DrawMountain {
-                x_pos,
-                ticks_ttl,
-                width,
-                velocity,
-            };
-            self.started_drawing = true;
-            false
-        } else {
-            self.intention = Intention::ChooseColor { tries: tries + 1 };
-            true
-        }
+        self.intention = Intention::DrawMountain {
+            x_pos,
+            ticks_ttl,
+            width,
+            velocity,
+        };
+        self.started_drawing = true;
+        self.phase = 0.0;
+        true
     }
 
-    fn tick(&mut self, mouse_pos: &mut (f32, f32)) {
+    fn tick(&mut self, mouse_pos: &mut (f32, f32), _: &mut Actions) {
         let Intention::DrawMountain {
             x_pos,
             ticks_ttl,
@@ -78,21 +73,12 @@ impl Strategy for Mountains {
             return;
         };
 
-        if self.started_drawing {
-            self.started_drawing = false;
-            mouse_pos.0 = x_pos;
-        }
-
-        let change = velocity * (if self.next_l_to_r { 1.0 } else { -1.0 });
-        mouse_pos.0 += change;
-
-        if mouse_pos.0 >= x_pos + width || mouse_pos.0 <= x_pos - width {
-            self.next_l_to_r = !self.next_l_to_r;
-        }
+        self.started_drawing = false;
+        mouse_pos.0 = x_pos;
+        self.phase += velocity;
 
         if ticks_ttl == 0 {
-            self.next_l_to_r = !self.next_l_to_r;
-            self.intention = Intention::ChooseColor { tries: 0 };
+            self.intention = Intention::ChooseColor;
         } else {
             self.intention = Intention::DrawMountain {
                 x_pos,
@@ -102,4 +88,14 @@ impl Strategy for Mountains {
             };
         }
     }
+
+    fn radius(&self, grid_width: f32) -> Option<f64> {
+        let Intention::DrawMountain { width, .. } = self.intention else {
+            return None;
+        };
+
+        // Breathe between a trickle and the full mountain width.
+        let spread = 0.5 - 0.5 * self.phase.cos();
+        Some((1.0 + width * grid_width * FULL_WIDTH_RADIUS * spread) as f64)
+    }
 }
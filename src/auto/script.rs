@@ -0,0 +1,253 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::{
+    auto::{Actions, Strategy},
+    gradient::{Gradient, Steps},
+};
+
+/// How often the script file is checked for changes.
+const RELOAD_TICKS: u32 = 60;
+const COLOR_STEPS: Steps = Steps::new(300).unwrap();
+
+/// A normalized coordinate, `?` in a script picks a random one each time.
+#[derive(Clone, Copy)]
+enum Coord {
+    Fixed(f32),
+    Random,
+}
+
+impl Coord {
+    fn get(self) -> f32 {
+        match self {
+            Coord::Fixed(v) => v,
+            Coord::Random => fastrand::f32(),
+        }
+    }
+}
+
+enum Step {
+    Spawn(bool),
+    Move { to: (Coord, Coord), ticks: u32 },
+    Wait(u32),
+    Radius(Option<f64>),
+    Color(u32, u32),
+    Palette,
+    Clear,
+    Emit(Coord, Coord),
+    Wind(f32),
+    Repeat,
+}
+
+fn parse_coord(token: &str) -> Result<Coord, String> {
+    if token == "?" {
+        return Ok(Coord::Random);
+    }
+
+    match token.parse::<f32>() {
+        Ok(v) if (0.0..=1.0).contains(&v) => Ok(Coord::Fixed(v)),
+        _ => Err(format!("expected a coordinate in 0..1 or ?, got {token:?}")),
+    }
+}
+
+fn parse_color(token: &str) -> Result<u32, String> {
+    let hex = token.trim_start_matches('#');
+    match u32::from_str_radix(hex, 16) {
+        Ok(rgb) if hex.len() == 6 => Ok(rgb),
+        _ => Err(format!("expected a color like ff8800, got {token:?}")),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(token: &str) -> Result<T, String> {
+    token
+        .parse()
+        .map_err(|_| format!("expected a number, got {token:?}"))
+}
+
+fn parse_step(words: &[&str]) -> Result<Step, String> {
+    let step = match *words {
+        ["spawn", "on"] => Step::Spawn(true),
+        ["spawn", "off"] => Step::Spawn(false),
+        ["move", x, y, ticks] => Step::Move {
+            to: (parse_coord(x)?, parse_coord(y)?),
+            ticks: parse_number::<u32>(ticks)?.max(1),
+        },
+        ["wait", ticks] => Step::Wait(parse_number(ticks)?),
+        ["radius", "off"] => Step::Radius(None),
+        ["radius", r] => Step::Radius(Some(parse_number::<f64>(r)?.max(0.5))),
+        ["color", c] => Step::Color(parse_color(c)?, parse_color(c)?),
+        ["color", start, end] => Step::Color(parse_color(start)?, parse_color(end)?),
+        ["palette"] => Step::Palette,
+        ["clear"] => Step::Clear,
+        ["emit", x, y] => Step::Emit(parse_coord(x)?, parse_coord(y)?),
+        ["wind", w] => Step::Wind(parse_number::<f32>(w)?.clamp(-1.0, 1.0)),
+        ["repeat"] => Step::Repeat,
+        _ => return Err(format!("unknown step {:?}", words.join(" "))),
+    };
+    Ok(step)
+}
+
+/// Parses a script, one step per line with `#` starting a comment.
+fn load(path: &Path) -> io::Result<Vec<Step>> {
+    let mut steps = vec![];
+    for (number, line) in fs::read_to_string(path)?.lines().enumerate() {
+        let words: Vec<_> = line.split('#').next().unwrap().split_whitespace().collect();
+        if words.is_empty() {
+            continue;
+        }
+
+        let step = parse_step(&words)
+            .map_err(|e| io::Error::other(format!("line {}: {e}", number + 1)))?;
+        steps.push(step);
+    }
+    Ok(steps)
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Plays back a timeline of steps from a file, reloading it whenever it changes.
+pub struct Script {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    ticks: u32,
+    steps: Vec<Step>,
+    /// Index of the current step, `steps.len()` once the script has ended.
+    current: usize,
+    /// Ticks spent in the current step.
+    progress: u32,
+    /// Where the cursor was and is going while the current step is a move.
+    from: (f32, f32),
+    to: (f32, f32),
+    spawning: bool,
+    radius: Option<f64>,
+    wind: f32,
+    new_palette: bool,
+}
+
+impl Script {
+    pub fn new(path: &Path) -> Self {
+        let mut script = Self {
+            path: path.to_owned(),
+            modified: None,
+            ticks: 0,
+            steps: vec![],
+            current: 0,
+            progress: 0,
+            from: (0.0, 0.0),
+            to: (0.0, 0.0),
+            spawning: false,
+            radius: None,
+            wind: 0.0,
+            new_palette: false,
+        };
+        script.reload();
+        script
+    }
+
+    /// Restarts from the top with the file's current contents, keeping the
+    /// old steps if the file does not parse.
+    fn reload(&mut self) {
+        self.modified = modified(&self.path);
+        match load(&self.path) {
+            Ok(steps) => {
+                self.steps = steps;
+                self.current = 0;
+                self.progress = 0;
+                self.spawning = false;
+                self.radius = None;
+                self.wind = 0.0;
+            }
+            Err(e) => eprintln!("could not load script {}: {e}", self.path.display()),
+        }
+    }
+
+    fn advance(&mut self) {
+        self.current += 1;
+        self.progress = 0;
+    }
+}
+
+impl Strategy for Script {
+    fn starting_pos(&self) -> (f32, f32) {
+        (0.5, 0.001)
+    }
+
+    fn should_spawn(&self) -> bool {
+        self.spawning
+    }
+
+    fn choose_new_color(&mut self) -> bool {
+        std::mem::take(&mut self.new_palette)
+    }
+
+    fn tick(&mut self, mouse_pos: &mut (f32, f32), actions: &mut Actions) {
+        self.ticks = self.ticks.wrapping_add(1);
+        if self.ticks.is_multiple_of(RELOAD_TICKS) && modified(&self.path) != self.modified {
+            self.reload();
+        }
+
+        // Instant steps run back to back, but a script without any waiting
+        // must not loop forever within a single tick.
+        for _ in 0..=self.steps.len() {
+            let Some(step) = self.steps.get(self.current) else {
+                self.spawning = false;
+                return;
+            };
+
+            match step {
+                Step::Move { to, ticks } => {
+                    if self.progress == 0 {
+                        self.from = *mouse_pos;
+                        self.to = (to.0.get(), to.1.get());
+                    }
+                    self.progress += 1;
+
+                    let t = self.progress as f32 / *ticks as f32;
+                    mouse_pos.0 = self.from.0 + (self.to.0 - self.from.0) * t;
+                    mouse_pos.1 = self.from.1 + (self.to.1 - self.from.1) * t;
+
+                    if self.progress >= *ticks {
+                        self.advance();
+                    }
+                    return;
+                }
+                Step::Wait(ticks) => {
+                    self.progress += 1;
+                    if self.progress >= *ticks {
+                        self.advance();
+                    }
+                    return;
+                }
+                Step::Spawn(spawning) => self.spawning = *spawning,
+                Step::Radius(radius) => self.radius = *radius,
+                Step::Color(start, end) => {
+                    actions.gradient = Some(Gradient::between(*start, *end, COLOR_STEPS));
+                }
+                Step::Palette => self.new_palette = true,
+                Step::Clear => actions.clear = true,
+                Step::Emit(x, y) => actions.spawns.push((x.get(), y.get())),
+                Step::Wind(wind) => self.wind = *wind,
+                Step::Repeat => {
+                    self.current = 0;
+                    self.progress = 0;
+                    continue;
+                }
+            }
+
+            self.advance();
+        }
+    }
+
+    fn radius(&self, _: f32) -> Option<f64> {
+        self.radius
+    }
+
+    fn wind(&self) -> f32 {
+        self.wind
+    }
+}
@@ -0,0 +1,187 @@
+use std::{f32::consts::TAU, sync::Arc};
+
+use crate::{
+    auto::{Actions, Strategy},
+    image::{alpha, Image},
+};
+
+/// Resolution the target skyline is stored at.
+const SAMPLES: usize = 257;
+/// Surface heights closer than this to the target count as done.
+const TOLERANCE: f32 = 0.01;
+const SPEED: f32 = 0.004;
+const RADIUS: f64 = 3.0;
+/// Ticks of spawning before switching to a new gradient.
+const LAYER_TICKS: u32 = 600;
+/// Ticks between looking for the column that is furthest behind.
+const RETARGET_TICKS: u32 = 30;
+
+/// Where the target skyline comes from.
+#[derive(Clone)]
+pub enum Profile {
+    /// A random sum of sine waves.
+    Waves,
+    /// Random terrain from midpoint displacement.
+    Terrain,
+    /// Fixed heights as fractions of the grid height, e.g. from an image.
+    Heights(Arc<[f32]>),
+}
+
+impl Profile {
+    /// Heights of an image's silhouette, the share of opaque pixels per column.
+    pub fn from_image(image: &Image) -> Self {
+        let heights = (0..image.width)
+            .map(|x| {
+                let opaque = (0..image.height)
+                    .filter(|y| alpha(image.pixels[y * image.width + x]) >= 128)
+                    .count();
+                opaque as f32 / image.height as f32
+            })
+            .collect();
+        Profile::Heights(heights)
+    }
+
+    fn generate(&self) -> Vec<f32> {
+        match self {
+            Profile::Waves => {
+                let waves: Vec<_> = (0..3)
+                    .map(|i| {
+                        let frequency = TAU * fastrand::u32(1..4 + i * 3) as f32;
+                        (frequency, fastrand::f32() * TAU, 0.15 / (i + 1) as f32)
+                    })
+                    .collect();
+
+                (0..SAMPLES)
+                    .map(|i| {
+                        let x = i as f32 / (SAMPLES - 1) as f32;
+                        let wave: f32 = waves.iter().map(|(f, p, a)| a * (f * x + p).sin()).sum();
+                        0.35 + wave
+                    })
+                    .collect()
+            }
+            Profile::Terrain => midpoint_displacement(),
+            Profile::Heights(heights) => (0..SAMPLES)
+                .map(|i| heights[i * (heights.len() - 1) / (SAMPLES - 1)])
+                .collect(),
+        }
+    }
+}
+
+/// Random terrain between 0.1 and 0.7 of the grid height.
+fn midpoint_displacement() -> Vec<f32> {
+    let mut heights = vec![0.0; SAMPLES];
+    heights[0] = fastrand::f32();
+    heights[SAMPLES - 1] = fastrand::f32();
+
+    let mut step = SAMPLES - 1;
+    let mut spread = 0.5;
+    while step > 1 {
+        for start in (0..SAMPLES - 1).step_by(step) {
+            let middle = start + step / 2;
+            let offset = (fastrand::f32() * 2.0 - 1.0) * spread;
+            heights[middle] = (heights[start] + heights[start + step]) / 2.0 + offset;
+        }
+        step /= 2;
+        spread *= 0.55;
+    }
+
+    let min = heights.iter().copied().fold(f32::INFINITY, f32::min);
+    let max = heights.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let range = (max - min).max(f32::EPSILON);
+    heights
+        .into_iter()
+        .map(|h| 0.1 + 0.6 * (h - min) / range)
+        .collect()
+}
+
+/// Fills the canvas until the resting sand matches a target skyline.
+pub struct Skyline {
+    target: Vec<f32>,
+    surface: Vec<f32>,
+    /// Normalized column the cursor is heading for.
+    goal: f32,
+    spawning: bool,
+    ticks: u32,
+    layer_ticks: u32,
+    new_layer: bool,
+}
+
+impl Skyline {
+    pub fn new(profile: &Profile) -> Self {
+        Self {
+            target: profile.generate(),
+            surface: vec![],
+            goal: 0.5,
+            spawning: false,
+            ticks: 0,
+            layer_ticks: 0,
+            new_layer: true,
+        }
+    }
+
+    /// How much lower the surface is than the target at normalized `x`.
+    fn deficit(&self, x: f32) -> f32 {
+        let column = ((x * self.surface.len() as f32) as usize).min(self.surface.len() - 1);
+        let sample = ((x * (SAMPLES - 1) as f32).round() as usize).min(SAMPLES - 1);
+        self.target[sample] - self.surface[column]
+    }
+
+    fn worst_column(&self) -> f32 {
+        let columns = self.surface.len();
+        (0..columns)
+            .map(|c| (c as f32 + 0.5) / columns as f32)
+            .max_by(|a, b| self.deficit(*a).total_cmp(&self.deficit(*b)))
+            .unwrap_or(0.5)
+    }
+}
+
+impl Strategy for Skyline {
+    fn starting_pos(&self) -> (f32, f32) {
+        (0.5, 0.001)
+    }
+
+    fn should_spawn(&self) -> bool {
+        self.spawning
+    }
+
+    fn choose_new_color(&mut self) -> bool {
+        std::mem::take(&mut self.new_layer)
+    }
+
+    fn wants_surface(&self) -> bool {
+        true
+    }
+
+    fn observe(&mut self, surface: &[f32]) {
+        self.surface.clear();
+        self.surface.extend_from_slice(surface);
+    }
+
+    fn tick(&mut self, mouse_pos: &mut (f32, f32), _: &mut Actions) {
+        if self.surface.is_empty() {
+            self.spawning = false;
+            return;
+        }
+
+        self.ticks = self.ticks.wrapping_add(1);
+        if self.ticks.is_multiple_of(RETARGET_TICKS) || self.deficit(self.goal) <= TOLERANCE {
+            self.goal = self.worst_column();
+        }
+
+        let step = (self.goal - mouse_pos.0).clamp(-SPEED, SPEED);
+        mouse_pos.0 += step;
+
+        self.spawning = self.deficit(mouse_pos.0) > TOLERANCE;
+        if self.spawning {
+            self.layer_ticks += 1;
+            if self.layer_ticks >= LAYER_TICKS {
+                self.layer_ticks = 0;
+                self.new_layer = true;
+            }
+        }
+    }
+
+    fn radius(&self, _: f32) -> Option<f64> {
+        Some(RADIUS)
+    }
+}
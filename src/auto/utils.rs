@@ -1,16 +1,23 @@
-fn brown_black_score(rgb: u32) -> f32 {
-    let r = ((rgb >> 16) & 0xFF) as f32 / 255.0;
-    let g = ((rgb >> 8) & 0xFF) as f32 / 255.0;
-    let b = (rgb & 0xFF) as f32 / 255.0;
+use std::collections::VecDeque;
+
+use crate::gradient::{mix, split_rgb, Gradient};
+
+/// Hue in degrees, saturation and lightness in `0.0..=1.0`.
+pub fn hsl(rgb: u32) -> (f32, f32, f32) {
+    let (r, g, b) = split_rgb(rgb);
+    let (r, g, b) = (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
 
-    // --- RGB -> HSV ---
     let max = r.max(g.max(b));
     let min = r.min(g.min(b));
     let delta = max - min;
 
-    let value = max;
+    let lightness = (max + min) / 2.0;
 
-    let saturation = if max == 0.0 { 0.0 } else { delta / max };
+    let saturation = if delta == 0.0 {
+        0.0
+    } else {
+        delta / (1.0 - (2.0 * lightness - 1.0).abs())
+    };
 
     let hue = if delta == 0.0 {
         0.0
@@ -24,23 +31,116 @@ fn brown_black_score(rgb: u32) -> f32 {
 
     let hue = if hue < 0.0 { hue + 360.0 } else { hue };
 
-    // --- Scoring ---
+    (hue, saturation, lightness)
+}
+
+/// Angle between two hues, `0.0..=180.0`.
+fn hue_distance(a: f32, b: f32) -> f32 {
+    let d = (a - b).abs() % 360.0;
+    d.min(360.0 - d)
+}
+
+/// Euclidean RGB distance scaled to `0.0..=1.0`.
+fn contrast(a: u32, b: u32) -> f32 {
+    let (a, b) = (split_rgb(a), split_rgb(b));
+    let d = |x: u8, y: u8| (x as f32 - y as f32) / 255.0;
+    ((d(a.0, b.0).powi(2) + d(a.1, b.1).powi(2) + d(a.2, b.2).powi(2)) / 3.0).sqrt()
+}
+
+/// How a new gradient's hue should relate to the previous one.
+#[derive(Clone, Copy)]
+pub enum Harmony {
+    Any,
+    Complementary,
+    Analogous,
+    Triadic,
+}
 
-    // Blackness: darker = higher
-    let blackness = 1.0 - value;
+impl Harmony {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "any" => Some(Harmony::Any),
+            "complementary" => Some(Harmony::Complementary),
+            "analogous" => Some(Harmony::Analogous),
+            "triadic" => Some(Harmony::Triadic),
+            _ => None,
+        }
+    }
 
-    // Brown ≈ dark orange (~20°–40°)
-    let target_hue = 30.0;
-    let hue_dist = ((hue - target_hue).abs() / 180.0).min(1.0);
-    let hue_score = 1.0 - hue_dist;
+    /// How far, in degrees, `distance` is from what the rule asks for.
+    fn miss(self, distance: f32) -> f32 {
+        let (low, high) = match self {
+            Harmony::Any => return 0.0,
+            Harmony::Complementary => (150.0, 180.0),
+            Harmony::Analogous => (15.0, 45.0),
+            Harmony::Triadic => (100.0, 140.0),
+        };
+        (low - distance).max(distance - high).max(0.0)
+    }
+}
 
-    // Brown needs some saturation and some darkness
-    let brownness = hue_score * saturation * (1.0 - value);
+/// Rules a new auto mode gradient should follow, every broken rule adds to
+/// its penalty and the candidate with the lowest one wins.
+#[derive(Clone)]
+pub struct ColorPolicy {
+    pub min_saturation: f32,
+    pub min_lightness: f32,
+    pub max_lightness: f32,
+    /// Minimum [`contrast`] to both ends of the previous gradient.
+    pub min_contrast: f32,
+    /// Minimum distance in degrees to the hues of the remembered gradients.
+    pub hue_gap: f32,
+    /// How many past gradients are remembered.
+    pub history: usize,
+    pub harmony: Harmony,
+}
 
-    // Combine (tweak weights as needed)
-    0.6 * blackness + 0.4 * brownness
+impl Default for ColorPolicy {
+    fn default() -> Self {
+        Self {
+            min_saturation: 0.25,
+            min_lightness: 0.25,
+            max_lightness: 0.9,
+            min_contrast: 0.15,
+            hue_gap: 20.0,
+            history: 4,
+            harmony: Harmony::Any,
+        }
+    }
 }
 
-pub fn is_nice_color(rgb: u32) -> bool {
-    brown_black_score(rgb) < 0.5
+impl ColorPolicy {
+    /// Zero for a gradient that follows every rule, `history` holds the ends
+    /// of past gradients with the newest at the front.
+    pub fn penalty(&self, candidate: &Gradient, history: &VecDeque<(u32, u32)>) -> f32 {
+        let (start, end) = candidate.ends();
+        let mut penalty = 0.0;
+
+        for color in [start, end] {
+            let (_, saturation, lightness) = hsl(color);
+            penalty += (self.min_saturation - saturation).max(0.0);
+            penalty += (self.min_lightness - lightness).max(0.0);
+            penalty += (lightness - self.max_lightness).max(0.0);
+        }
+
+        let (hue, _, _) = hsl(mix(start, end));
+
+        if let Some((previous_start, previous_end)) = history.front() {
+            for a in [start, end] {
+                for b in [*previous_start, *previous_end] {
+                    penalty += (self.min_contrast - contrast(a, b)).max(0.0);
+                }
+            }
+
+            let (previous_hue, _, _) = hsl(mix(*previous_start, *previous_end));
+            penalty += self.harmony.miss(hue_distance(hue, previous_hue)) / 180.0;
+        }
+
+        for (previous_start, previous_end) in history {
+            let (previous_hue, _, _) = hsl(mix(*previous_start, *previous_end));
+            penalty += (self.hue_gap - hue_distance(hue, previous_hue)).max(0.0) / 180.0;
+        }
+
+        penalty
+    }
 }
@@ -0,0 +1,12 @@
+/// Rows per tick added to a falling body's speed every tick when gravity is off.
+pub const BODY_GRAVITY: f32 = 0.2;
+
+/// Cells of [`crate::material::BLOCK`] that fall as one solid piece until
+/// they land, see [`crate::grid::Grid::drop_body`].
+#[derive(Clone)]
+pub struct Body {
+    /// Position of every cell, lowest first.
+    pub cells: Vec<(u16, u16)>,
+    /// Rows per tick the body is falling at.
+    pub velocity: f32,
+}
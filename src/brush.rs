@@ -0,0 +1,128 @@
+use std::sync::Arc;
+
+use crate::image::{alpha, Image};
+
+const SPRAY_DENSITY: f32 = 0.15;
+
+/// Footprint of a single stamp, shared by spawning and the cursor preview.
+#[derive(Clone)]
+pub enum Brush {
+    Circle,
+    Square,
+    Line,
+    Spray { density: f32 },
+    Ring,
+    Bitmap(Arc<Image>),
+}
+
+/// Cheap integer hash, spray needs a different but reproducible pattern per stamp.
+fn hash(x: isize, y: isize, seed: u32) -> u32 {
+    let mut h = (x as u32).wrapping_mul(0x9E37_79B1)
+        ^ (y as u32).wrapping_mul(0x85EB_CA77)
+        ^ seed.wrapping_mul(0xC2B2_AE3D);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x2C1B_3C6D);
+    h ^= h >> 12;
+    h
+}
+
+impl Brush {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Brush::Circle => "circle",
+            Brush::Square => "square",
+            Brush::Line => "line",
+            Brush::Spray { .. } => "spray",
+            Brush::Ring => "ring",
+            Brush::Bitmap(_) => "bitmap",
+        }
+    }
+
+    /// The brush after this one, `bitmap` is only offered if one was loaded.
+    pub fn next(&self, bitmap: Option<&Arc<Image>>) -> Brush {
+        match self {
+            Brush::Circle => Brush::Square,
+            Brush::Square => Brush::Line,
+            Brush::Line => Brush::Spray {
+                density: SPRAY_DENSITY,
+            },
+            Brush::Spray { .. } => Brush::Ring,
+            Brush::Ring => match bitmap {
+                Some(bitmap) => Brush::Bitmap(bitmap.clone()),
+                None => Brush::Circle,
+            },
+            Brush::Bitmap(_) => Brush::Circle,
+        }
+    }
+
+    fn contains(&self, x: isize, y: isize, radius: f64, seed: u32) -> bool {
+        let sq_distance = (x * x + y * y) as f64;
+        match self {
+            Brush::Circle => sq_distance <= radius * radius,
+            Brush::Square => true,
+            Brush::Line => y == 0,
+            Brush::Spray { density } => {
+                sq_distance <= radius * radius
+                    && (hash(x, y, seed) as f32) < density * u32::MAX as f32
+            }
+            Brush::Ring => {
+                let inner = (radius - (radius / 4.0).max(1.0)).max(0.0);
+                sq_distance <= radius * radius && sq_distance > inner * inner
+            }
+            Brush::Bitmap(image) => alpha(sample(image, x, y, radius)) >= 128,
+        }
+    }
+
+    /// Color of the bitmap at offset `x`, `y` of a stamp of `radius`, `None`
+    /// for brushes without colors of their own.
+    pub fn color_at(&self, x: isize, y: isize, radius: f64) -> Option<u32> {
+        match self {
+            Brush::Bitmap(image) => Some(sample(image, x, y, radius) & 0xFFFFFF),
+            _ => None,
+        }
+    }
+
+    /// Cell offsets covered by a stamp of `radius` around its center.
+    pub fn offsets(&self, radius: f64, seed: u32) -> impl Iterator<Item = (isize, isize)> + '_ {
+        let radius_ceil = radius.ceil() as isize;
+
+        (-radius_ceil + 1..radius_ceil)
+            .flat_map(move |y| (-radius_ceil + 1..radius_ceil).map(move |x| (x, y)))
+            .filter(move |(x, y)| self.contains(*x, *y, radius, seed))
+    }
+
+    /// Cell offsets the cursor preview covers for a stamp of `radius`. Every
+    /// spray stamp is different, so it shows the disc they land in.
+    pub fn preview_offsets(&self, radius: f64) -> impl Iterator<Item = (isize, isize)> + '_ {
+        let footprint = match self {
+            Brush::Spray { .. } => &Brush::Circle,
+            brush => brush,
+        };
+        footprint.offsets(radius, 0)
+    }
+}
+
+/// The pixel of `image` at offset `x`, `y` of a stamp of `radius` it's stretched over.
+fn sample(image: &Image, x: isize, y: isize, radius: f64) -> u32 {
+    let side = 2.0 * radius.ceil() as f32 - 1.0;
+    let offset = radius.ceil() as f32 - 0.5;
+    image.sample((x as f32 + offset) / side, (y as f32 + offset) / side)
+}
+
+/// Stamp centers from just past `from` up to and including `to`, at most
+/// `spacing` cells apart so fast strokes stay continuous.
+pub fn stroke(from: (u16, u16), to: (u16, u16), spacing: f64) -> impl Iterator<Item = (u16, u16)> {
+    let dx = to.0 as f64 - from.0 as f64;
+    let dy = to.1 as f64 - from.1 as f64;
+    let stamps = ((dx * dx + dy * dy).sqrt() / spacing.max(1.0))
+        .ceil()
+        .max(1.0) as u32;
+
+    (1..=stamps).map(move |i| {
+        let t = i as f64 / stamps as f64;
+        (
+            (from.0 as f64 + dx * t).round() as u16,
+            (from.1 as f64 + dy * t).round() as u16,
+        )
+    })
+}
@@ -0,0 +1,120 @@
+use std::{path::PathBuf, process::exit, str::FromStr, time::Duration};
+
+use crate::auto::{find_strategy, strategy_names};
+
+const USAGE: &str = "\
+usage: megalodon [options]
+
+options:
+    --config <file>     read settings from a `key = value` file
+    --palette <png>     draw with gradients sampled from the dominant colors of an image
+    --image <png>       start with an image as sand, press I to drop it again
+    --threshold <0-255> leave pixels this close to the image's corner color empty
+    --brush <png>       add the image's silhouette to the brushes cycled with B
+    --strategy <name>   auto mode strategy to start with, cycle with N
+    --playlist <secs>   switch to the next auto strategy after this many seconds
+    --emitters <n>      number of cursors auto mode draws with side by side
+    --script <file>     timeline for the script strategy, reloaded when it changes
+    --profile <shape>   skyline strategy target: terrain, waves or a png's silhouette
+    -h, --help          print this message";
+
+pub struct Args {
+    pub config: Option<PathBuf>,
+    pub palette: Option<PathBuf>,
+    pub image: Option<PathBuf>,
+    pub threshold: Option<u8>,
+    pub brush: Option<PathBuf>,
+    pub strategy: usize,
+    pub playlist: Option<Duration>,
+    pub script: Option<PathBuf>,
+    pub emitters: usize,
+    pub profile: Option<String>,
+}
+
+fn value(args: &mut impl Iterator<Item = String>, flag: &str) -> String {
+    args.next()
+        .unwrap_or_else(|| fail(&format!("{flag} expects a value")))
+}
+
+fn number<T: FromStr>(args: &mut impl Iterator<Item = String>, flag: &str) -> T {
+    let value = value(args, flag);
+    value
+        .parse()
+        .unwrap_or_else(|_| fail(&format!("invalid value {value} for {flag}")))
+}
+
+pub fn fail(message: &str) -> ! {
+    eprintln!("megalodon: {message}\n\n{USAGE}");
+    exit(2)
+}
+
+impl Args {
+    pub fn parse() -> Self {
+        let mut parsed = Args {
+            config: None,
+            palette: None,
+            image: None,
+            threshold: None,
+            brush: None,
+            strategy: 0,
+            playlist: None,
+            script: None,
+            emitters: 1,
+            profile: None,
+        };
+        let mut args = std::env::args().skip(1);
+        let mut strategy = None;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--config" => parsed.config = Some(value(&mut args, &arg).into()),
+                "--palette" => parsed.palette = Some(value(&mut args, &arg).into()),
+                "--image" => parsed.image = Some(value(&mut args, &arg).into()),
+                "--threshold" => parsed.threshold = Some(number(&mut args, &arg)),
+                "--brush" => parsed.brush = Some(value(&mut args, &arg).into()),
+                "--strategy" => {
+                    let name = value(&mut args, &arg);
+                    strategy = Some(find_strategy(&name).unwrap_or_else(|| {
+                        let names = strategy_names().collect::<Vec<_>>().join(", ");
+                        fail(&format!("unknown strategy {name}, expected one of {names}"))
+                    }));
+                }
+                "--playlist" => {
+                    let secs: f32 = number(&mut args, &arg);
+                    // Shorter ones switch strategies before they draw anything.
+                    if !(1.0..).contains(&secs) {
+                        fail("--playlist must be at least 1 second");
+                    }
+                    let playlist = Duration::try_from_secs_f32(secs)
+                        .unwrap_or_else(|_| fail(&format!("invalid value {secs} for {arg}")));
+                    parsed.playlist = Some(playlist);
+                }
+                "--emitters" => {
+                    parsed.emitters = number(&mut args, &arg);
+                    if parsed.emitters == 0 {
+                        fail("--emitters must be at least 1");
+                    }
+                }
+                "--script" => parsed.script = Some(value(&mut args, &arg).into()),
+                "--profile" => parsed.profile = Some(value(&mut args, &arg)),
+                "-h" | "--help" => {
+                    println!("{USAGE}");
+                    exit(0)
+                }
+                _ => fail(&format!("unknown argument {arg}")),
+            }
+        }
+
+        let script = find_strategy("script").unwrap();
+        parsed.strategy = match strategy {
+            Some(strategy) if strategy == script && parsed.script.is_none() => {
+                fail("the script strategy needs --script")
+            }
+            Some(strategy) => strategy,
+            None if parsed.script.is_some() => script,
+            None => 0,
+        };
+
+        parsed
+    }
+}
@@ -0,0 +1,346 @@
+use std::{fs, io, path::Path, str::FromStr};
+
+use crate::{
+    auto::utils::{ColorPolicy, Harmony},
+    grid::{Edge, Edges, Physics},
+    material::{self, Material, MaterialId, BACKGROUND},
+    radii::{Radius, RADII},
+    reaction::Reactions,
+};
+
+/// Settings read from a `key = value` file, `#` starts a comment.
+pub struct Config {
+    pub radius: f64,
+    pub radius_min: f64,
+    pub radius_max: f64,
+    pub radii: Vec<f64>,
+    pub colors: ColorPolicy,
+    /// Indexed by [`material::MaterialId`].
+    pub materials: Vec<Material>,
+    pub physics: Physics,
+    pub edges: Edges,
+    pub reactions: Reactions,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let radius = Radius::default();
+        let materials = material::defaults();
+        Self {
+            radius: radius.get(),
+            radius_min: RADII[0],
+            radius_max: RADII[RADII.len() - 1],
+            radii: RADII.to_vec(),
+            colors: ColorPolicy::default(),
+            reactions: Reactions::defaults(&materials),
+            materials,
+            physics: Physics::default(),
+            edges: Edges::default(),
+        }
+    }
+}
+
+fn parse<T: FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value {value:?}"))
+}
+
+/// A chance in `0.0..=1.0`.
+fn chance(value: &str) -> Result<f32, String> {
+    match parse(value)? {
+        chance if (0.0..=1.0).contains(&chance) => Ok(chance),
+        _ => Err(format!("expected a value between 0 and 1, got {value:?}")),
+    }
+}
+
+fn edge(value: &str) -> Result<Edge, String> {
+    Edge::from_name(value).ok_or_else(|| format!("expected solid, wrap or open, got {value:?}"))
+}
+
+/// A range of ticks like `300..600`, or `none` to live forever.
+fn lifetime(value: &str) -> Result<Option<(u16, u16)>, String> {
+    if value == "none" {
+        return Ok(None);
+    }
+
+    let invalid = || format!("expected `shortest..longest` or none, got {value:?}");
+    let (shortest, longest) = value.split_once("..").ok_or_else(invalid)?;
+    match (parse(shortest.trim())?, parse(longest.trim())?) {
+        (shortest, longest) if 0 < shortest && shortest <= longest => Ok(Some((shortest, longest))),
+        _ => Err(invalid()),
+    }
+}
+
+/// A `#rrggbb` color, `background` to fade out, or `none`.
+fn ages_to(value: &str) -> Result<Option<u32>, String> {
+    match value {
+        "none" => Ok(None),
+        "background" => Ok(Some(BACKGROUND)),
+        _ => value
+            .strip_prefix('#')
+            .filter(|hex| hex.len() == 6)
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .map(Some)
+            .ok_or_else(|| format!("expected #rrggbb, background or none, got {value:?}")),
+    }
+}
+
+/// A temperature, or `none` for cells that don't hold one.
+fn heat(value: &str) -> Result<Option<f32>, String> {
+    match value {
+        "none" => Ok(None),
+        _ => parse(value).map(Some),
+    }
+}
+
+/// A temperature and the material cells turn into past it, like
+/// `900 glass`, or `none`.
+fn transition(value: &str, materials: &[Material]) -> Result<Option<(f32, MaterialId)>, String> {
+    if value == "none" {
+        return Ok(None);
+    }
+
+    let (temperature, name) = value
+        .split_once(' ')
+        .ok_or_else(|| format!("expected `temperature material` or none, got {value:?}"))?;
+    let into = materials
+        .iter()
+        .position(|m| m.name == name.trim())
+        .ok_or_else(|| format!("unknown material {:?}", name.trim()))?;
+    Ok(Some((parse(temperature)?, into as MaterialId)))
+}
+
+fn list<T: FromStr>(value: &str) -> Result<Vec<T>, String> {
+    value.split(',').map(|v| parse(v.trim())).collect()
+}
+
+impl Config {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let mut config = Config::default();
+
+        for (number, line) in fs::read_to_string(path)?.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let result = match line.split_once('=') {
+                Some((key, value)) => config.set(key.trim(), value.trim()),
+                None => Err("expected `key = value`".to_owned()),
+            };
+
+            result.map_err(|e| io::Error::other(format!("line {}: {e}", number + 1)))?;
+        }
+
+        if !(config.radius_min > 0.0 && config.radius_min <= config.radius_max) {
+            return Err(io::Error::other(
+                "radius.min must be positive and at most radius.max",
+            ));
+        }
+
+        let physics = &config.physics;
+        if !(physics.gravity >= 0.0 && physics.terminal_velocity >= 1.0) {
+            return Err(io::Error::other(
+                "physics.gravity must not be negative and physics.terminal_velocity at least 1",
+            ));
+        }
+
+        Ok(config)
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "radius" => self.radius = parse(value)?,
+            "radius.min" => self.radius_min = parse(value)?,
+            "radius.max" => self.radius_max = parse(value)?,
+            "radius.table" => self.radii = list(value)?,
+            "color.min_saturation" => self.colors.min_saturation = parse(value)?,
+            "color.min_lightness" => self.colors.min_lightness = parse(value)?,
+            "color.max_lightness" => self.colors.max_lightness = parse(value)?,
+            "color.min_contrast" => self.colors.min_contrast = parse(value)?,
+            "color.hue_gap" => self.colors.hue_gap = parse(value)?,
+            "color.history" => self.colors.history = parse(value)?,
+            "color.harmony" => {
+                self.colors.harmony = Harmony::from_name(value).ok_or_else(|| {
+                    format!("expected any, complementary, analogous or triadic, got {value:?}")
+                })?
+            }
+            "physics.gravity" => self.physics.gravity = parse(value)?,
+            "physics.terminal_velocity" => self.physics.terminal_velocity = parse(value)?,
+            "physics.splash" => self.physics.splash = parse(value)?,
+            "physics.shatter" => self.physics.shatter = parse(value)?,
+            "physics.diffusion" => self.physics.diffusion = chance(value)?,
+            "physics.cooling" => self.physics.cooling = chance(value)?,
+            "edge.left" => self.edges.left = edge(value)?,
+            "edge.right" => self.edges.right = edge(value)?,
+            "edge.top" => self.edges.top = edge(value)?,
+            "edge.bottom" => self.edges.bottom = edge(value)?,
+            "edge.sides" => {
+                self.edges.left = edge(value)?;
+                self.edges.right = self.edges.left;
+            }
+            "reactions" => {
+                self.reactions = Reactions::load(Path::new(value), &self.materials)
+                    .map_err(|e| format!("could not load reactions {value}: {e}"))?
+            }
+            _ => return self.set_material(key, value),
+        }
+        Ok(())
+    }
+
+    /// Sets `<material>.<parameter>` keys, e.g. `sand.slide`.
+    fn set_material(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let unknown = || format!("unknown key {key:?}");
+        let (name, parameter) = key.split_once('.').ok_or_else(unknown)?;
+        let id = self
+            .materials
+            .iter()
+            .position(|m| m.name == name)
+            .ok_or_else(unknown)?;
+        // Looked up before borrowing the material, it may name itself.
+        let transition = match parameter {
+            "melts" | "freezes" => transition(value, &self.materials)?,
+            _ => None,
+        };
+        let material = &mut self.materials[id];
+
+        match parameter {
+            "stickiness" => material.stickiness = chance(value)?,
+            "slide" => material.slide = chance(value)?,
+            "density" => material.density = parse(value)?,
+            "lifetime" => material.lifetime = lifetime(value)?,
+            "ages_to" => material.ages_to = ages_to(value)?,
+            "temperature" => material.temperature = Some(parse(value)?),
+            "heat" => material.heat = heat(value)?,
+            "melts" => material.melts = transition,
+            "freezes" => material.freezes = transition,
+            _ => return Err(unknown()),
+        }
+        Ok(())
+    }
+
+    pub fn radius(&self) -> Radius {
+        Radius::new(
+            self.radius,
+            self.radius_min,
+            self.radius_max,
+            self.radii.clone(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::{GLASS, ICE, SAND, SMOKE, WATER};
+
+    #[test]
+    fn valid_keys_are_applied() {
+        let mut config = Config::default();
+        let lines = [
+            ("radius", "4.5"),
+            ("physics.diffusion", "0.5"),
+            ("edge.sides", "wrap"),
+            ("edge.top", "open"),
+            ("sand.lifetime", "10..20"),
+            ("sand.ages_to", "#102030"),
+            ("smoke.ages_to", "background"),
+            ("smoke.lifetime", "none"),
+            ("water.density", "1.2"),
+            ("water.temperature", "-5"),
+            ("water.freezes", "-10 ice"),
+            ("water.melts", "none"),
+            ("sand.heat", "300"),
+            ("sand.melts", "700 glass"),
+        ];
+        for (key, value) in lines {
+            config.set(key, value).unwrap();
+        }
+
+        assert_eq!(config.radius, 4.5);
+        assert_eq!(config.physics.diffusion, 0.5);
+        assert!(config.edges.left == Edge::Wrap && config.edges.right == Edge::Wrap);
+        assert!(config.edges.top == Edge::Open && config.edges.bottom == Edge::Solid);
+
+        let sand = &config.materials[SAND as usize];
+        assert_eq!(sand.lifetime, Some((10, 20)));
+        assert_eq!(sand.ages_to, Some(0x102030));
+        assert_eq!(sand.heat, Some(300.0));
+        assert_eq!(sand.melts, Some((700.0, GLASS)));
+        let smoke = &config.materials[SMOKE as usize];
+        assert_eq!(smoke.ages_to, Some(BACKGROUND));
+        assert_eq!(smoke.lifetime, None);
+        let water = &config.materials[WATER as usize];
+        assert_eq!(water.density, 1.2);
+        assert_eq!(water.temperature, Some(-5.0));
+        assert_eq!(water.freezes, Some((-10.0, ICE)));
+        assert_eq!(water.melts, None);
+    }
+
+    #[test]
+    fn invalid_keys_and_values_are_reported() {
+        let cases = [
+            ("speed", "1", "unknown key \"speed\""),
+            ("mud.slide", "0.5", "unknown key \"mud.slide\""),
+            ("sand.colour", "red", "unknown key \"sand.colour\""),
+            ("radius", "big", "invalid value \"big\""),
+            ("radius.table", "1, x", "invalid value \"x\""),
+            (
+                "physics.diffusion",
+                "2",
+                "expected a value between 0 and 1, got \"2\"",
+            ),
+            (
+                "sand.stickiness",
+                "-1",
+                "expected a value between 0 and 1, got \"-1\"",
+            ),
+            (
+                "edge.top",
+                "sticky",
+                "expected solid, wrap or open, got \"sticky\"",
+            ),
+            (
+                "color.harmony",
+                "loud",
+                "expected any, complementary, analogous or triadic, got \"loud\"",
+            ),
+            (
+                "sand.lifetime",
+                "5",
+                "expected `shortest..longest` or none, got \"5\"",
+            ),
+            (
+                "sand.lifetime",
+                "9..3",
+                "expected `shortest..longest` or none, got \"9..3\"",
+            ),
+            ("sand.lifetime", "1..x", "invalid value \"x\""),
+            (
+                "sand.ages_to",
+                "#12345",
+                "expected #rrggbb, background or none, got \"#12345\"",
+            ),
+            (
+                "sand.ages_to",
+                "red",
+                "expected #rrggbb, background or none, got \"red\"",
+            ),
+            ("sand.temperature", "hot", "invalid value \"hot\""),
+            ("sand.heat", "hot", "invalid value \"hot\""),
+            (
+                "sand.melts",
+                "900",
+                "expected `temperature material` or none, got \"900\"",
+            ),
+            ("sand.melts", "900 mud", "unknown material \"mud\""),
+            ("water.freezes", "cold ice", "invalid value \"cold\""),
+        ];
+
+        for (key, value, error) in cases {
+            let result = Config::default().set(key, value).err();
+            assert_eq!(result.as_deref(), Some(error), "{key} = {value}");
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use std::{
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU32, Ordering},
         mpsc::Receiver,
         Mutex,
     },
@@ -8,26 +8,134 @@ use std::{
 };
 
 use crate::{
-    gradient::Gradient,
-    grid::{circle_offsets, Grid, EMPTY},
-    radii::RadiusId,
+    brush::Brush,
+    fixture::Fixture,
+    gradient::{blend, Gradient},
+    grid::{Edges, Grid, Physics, Stats, AMBIENT, EMPTY},
+    image::Image,
+    material::{Material, MaterialId},
+    reaction::Reactions,
 };
 
 pub enum Event {
     Clear,
     Exit,
     Resize(u16, u16),
-    Spawn(u32, (u16, u16)),
-    Radius(RadiusId),
+    Spawn {
+        color: u32,
+        position: (u16, u16),
+        radius: f64,
+        material: MaterialId,
+    },
+    Image(Image, Option<u8>),
+    Brush(Brush),
+    Wind(f32),
+    Place(Fixture),
+    /// Removes the fixtures within the given number of cells of a position.
+    RemoveFixtures((u16, u16), u16),
+    /// Drops the brush's stamp as a solid body.
+    DropBody {
+        color: u32,
+        position: (u16, u16),
+        radius: f64,
+    },
 }
 
 static CHANGED: AtomicBool = AtomicBool::new(false);
 static PIXELS: Mutex<Vec<u32>> = Mutex::new(Vec::new());
 static CHECKED: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+/// Temperatures as colors, published while [`SHOW_HEAT`] is set.
+static HEAT: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+/// Draws [`HEAT`] instead of the sand.
+static SHOW_HEAT: AtomicBool = AtomicBool::new(false);
+/// Resting sand height per column as a fraction of the grid height.
+static SURFACE: Mutex<Vec<f32>> = Mutex::new(Vec::new());
+/// Set while auto mode uses the surface, so it's only computed when needed.
+static SURFACE_WANTED: AtomicBool = AtomicBool::new(false);
+/// Fixtures in the grid, for drawing their markers.
+static FIXTURES: Mutex<Vec<Fixture>> = Mutex::new(Vec::new());
+static STATS: Mutex<Option<Stats>> = Mutex::new(None);
+static STATS_WANTED: AtomicBool = AtomicBool::new(false);
+/// Ticks between refreshing [`STATS`], they need a few passes over the grid.
+const STATS_TICKS: u32 = 30;
+/// Duration of the last update tick in microseconds.
+static TICK_TIME: AtomicU32 = AtomicU32::new(0);
+
+pub fn tick_time() -> Duration {
+    Duration::from_micros(TICK_TIME.load(Ordering::Relaxed) as u64)
+}
+
+/// The latest [`SURFACE`], which is kept up to date from now on if `wanted`
+/// and dropped otherwise.
+pub fn surface(wanted: bool) -> Vec<f32> {
+    SURFACE_WANTED.store(wanted, Ordering::Relaxed);
+    if !wanted {
+        return vec![];
+    }
+    SURFACE.lock().unwrap().clone()
+}
+
+pub fn toggle_heat() {
+    SHOW_HEAT.fetch_xor(true, Ordering::Relaxed);
+}
+
+/// Dark at [`AMBIENT`], blue when colder and red through yellow when hotter.
+fn heat_color(temperature: f32) -> u32 {
+    const NEUTRAL: u32 = 0x101010;
+    if temperature < AMBIENT {
+        blend(NEUTRAL, 0x40A0FF, ((AMBIENT - temperature) / 80.0).min(1.0))
+    } else if temperature < 400.0 {
+        blend(
+            NEUTRAL,
+            0xFF2000,
+            (temperature - AMBIENT) / (400.0 - AMBIENT),
+        )
+    } else {
+        blend(0xFF2000, 0xFFF080, ((temperature - 400.0) / 800.0).min(1.0))
+    }
+}
+
+fn publish_heat(grid: &Grid) {
+    let mut heat = HEAT.lock().unwrap();
+    heat.clear();
+    heat.extend(grid.temperatures().iter().map(|&t| heat_color(t)));
+}
+
+pub fn fixtures() -> Vec<Fixture> {
+    FIXTURES.lock().unwrap().clone()
+}
+
+/// Latest summary of the grid, `None` until the first one is computed.
+pub fn stats() -> Option<Stats> {
+    STATS_WANTED.store(true, Ordering::Relaxed);
+    STATS.lock().unwrap().clone()
+}
+
+/// Measures the surface again if the grid `changed` or it isn't measured yet.
+fn publish_surface(grid: &Grid, changed: bool) {
+    let mut surface = SURFACE.lock().unwrap();
+    if !SURFACE_WANTED.load(Ordering::Relaxed) {
+        surface.clear();
+        return;
+    }
+    if !changed && !surface.is_empty() {
+        return;
+    }
+
+    let (width, height) = grid.size();
+    surface.clear();
+    surface.extend((0..width).map(|x| grid.surface_height(x) as f32 / height as f32));
+}
 
-pub fn update_thread(recv: Receiver<Event>) {
+pub fn update_thread(
+    recv: Receiver<Event>,
+    materials: Vec<Material>,
+    reactions: Reactions,
+    physics: Physics,
+    edges: Edges,
+) {
     let sleep_time = Duration::from_secs(1) / 120;
-    let mut grid = Grid::new();
+    let mut grid = Grid::new(materials, reactions, physics, edges);
     {
         PIXELS.lock().unwrap().clone_from(&grid.colors);
         #[cfg(debug_assertions)]
@@ -37,6 +145,7 @@ pub fn update_thread(recv: Receiver<Event>) {
 
     let mut i = 0;
     let mut needs_update = false;
+    let mut showed_heat = false;
     loop {
         let start = Instant::now();
 
@@ -50,17 +159,47 @@ pub fn update_thread(recv: Receiver<Event>) {
                 Event::Resize(width, height) => {
                     needs_update = true;
                     grid.resize(width, height);
+                    *FIXTURES.lock().unwrap() = grid.fixtures().to_vec();
+                }
+                Event::Spawn {
+                    color,
+                    position,
+                    radius,
+                    material,
+                } => {
+                    needs_update |= grid.spawn(position, color, radius, material);
+                }
+                Event::Image(image, threshold) => {
+                    needs_update = grid.load_image(&image, threshold);
                 }
-                Event::Spawn(color, pos) => {
-                    needs_update |= grid.spawn(pos, color);
+                Event::Brush(brush) => {
+                    grid.set_brush(brush);
                 }
-                Event::Radius(r) => {
-                    grid.set_radius(r.get());
+                Event::Wind(wind) => {
+                    grid.set_wind(wind);
+                }
+                Event::Place(fixture) => {
+                    needs_update = true;
+                    grid.place(fixture);
+                    *FIXTURES.lock().unwrap() = grid.fixtures().to_vec();
+                }
+                Event::DropBody {
+                    color,
+                    position,
+                    radius,
+                } => {
+                    needs_update |= grid.drop_body(position, color, radius);
+                }
+                Event::RemoveFixtures(position, reach) => {
+                    needs_update = true;
+                    grid.remove_fixtures(position, reach);
+                    *FIXTURES.lock().unwrap() = grid.fixtures().to_vec();
                 }
                 Event::Exit => {
                     #[cfg(debug_assertions)]
                     std::mem::take(&mut *CHECKED.lock().unwrap());
                     std::mem::take(&mut *PIXELS.lock().unwrap());
+                    std::mem::take(&mut *HEAT.lock().unwrap());
                     return;
                 }
             }
@@ -72,16 +211,27 @@ pub fn update_thread(recv: Receiver<Event>) {
             updated = true;
         }
 
-        if cleared || updated {
+        let show_heat = SHOW_HEAT.load(Ordering::Relaxed);
+        if cleared || updated || show_heat != showed_heat {
             {
                 #[cfg(debug_assertions)]
                 CHECKED.lock().unwrap().clone_from(&grid.checked);
                 PIXELS.lock().unwrap().clone_from(&grid.colors);
             }
+            if show_heat {
+                publish_heat(&grid);
+            }
             CHANGED.store(true, Ordering::Relaxed);
         }
+        publish_surface(&grid, cleared || updated);
+        showed_heat = show_heat;
+
+        if i % STATS_TICKS == 0 && STATS_WANTED.load(Ordering::Relaxed) {
+            *STATS.lock().unwrap() = Some(grid.stats());
+        }
 
         let elapsed = start.elapsed();
+        TICK_TIME.store(elapsed.as_micros() as u32, Ordering::Relaxed);
 
         if elapsed < sleep_time {
             std::thread::sleep(sleep_time - elapsed);
@@ -93,32 +243,43 @@ pub fn update_thread(recv: Receiver<Event>) {
     }
 }
 
+/// Draws `color` over pixel `i` until the next frame arrives or the pixel is restored.
+pub fn overlay(buffer: &mut [u32], temporaries: &mut Vec<(usize, u32)>, i: usize, color: u32) {
+    temporaries.push((i, buffer[i]));
+    buffer[i] = color;
+}
+
 #[expect(clippy::too_many_arguments)]
 pub fn render_to(
     buffer: &mut Vec<u32>,
-    temporaries: &mut Vec<usize>,
+    temporaries: &mut Vec<(usize, u32)>,
     mouse_in_window: bool,
     gradient: &Gradient,
     mouse_position: (u16, u16),
     width: u16,
     height: u16,
+    brush: &Brush,
     radius: f64,
 ) {
+    let mut swapped = false;
     if CHANGED.swap(false, Ordering::Relaxed) {
-        if !cfg!(debug_assertions) {
-            let mut lock = PIXELS.lock().unwrap();
-            if lock.len() == buffer.len() {
-                std::mem::swap(buffer, &mut *lock);
-            }
+        let mut lock = if SHOW_HEAT.load(Ordering::Relaxed) {
+            HEAT.lock().unwrap()
+        } else if !cfg!(debug_assertions) {
+            PIXELS.lock().unwrap()
         } else {
-            let mut lock = CHECKED.lock().unwrap();
-            if lock.len() == buffer.len() {
-                std::mem::swap(buffer, &mut *lock);
-            }
+            CHECKED.lock().unwrap()
+        };
+        if lock.len() == buffer.len() {
+            std::mem::swap(buffer, &mut *lock);
+            swapped = true;
         }
-    } else {
-        for i in temporaries.iter().copied() {
-            buffer[i] = EMPTY;
+    }
+
+    if !swapped {
+        // Later overlays may cover earlier ones, so undo them newest first.
+        for (i, color) in temporaries.iter().rev().copied() {
+            buffer[i] = color;
         }
     }
 
@@ -126,15 +287,14 @@ pub fn render_to(
 
     if mouse_in_window {
         let color = gradient.peek_color();
-        for (dx, dy) in circle_offsets(radius) {
+        for (dx, dy) in brush.preview_offsets(radius) {
             let x = mouse_position.0 as isize + dx;
             let y = mouse_position.1 as isize + dy;
 
             if x >= 0 && x < width as isize && y >= 0 && y < height as isize {
                 let i = (y * width as isize + x) as usize;
                 if buffer[i] == EMPTY {
-                    temporaries.push(i);
-                    buffer[i] = color;
+                    overlay(buffer, temporaries, i, color);
                 }
             }
         }
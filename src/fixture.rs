@@ -0,0 +1,64 @@
+use crate::{double::overlay, material::MaterialId};
+
+/// Cells a drain empties in every direction around its position.
+pub const DRAIN_REACH: u16 = 1;
+
+const SOURCE_MARKER: u32 = 0xFFFFFF;
+const DRAIN_MARKER: u32 = 0x000000;
+
+/// Something placed in the scene that keeps working without the mouse.
+#[derive(Clone, Copy)]
+pub enum Fixture {
+    /// Spawns a cell of `material` at `position` every tick it's free, drawn
+    /// with `color` unless the material has colors of its own.
+    Source {
+        position: (u16, u16),
+        material: MaterialId,
+        color: u32,
+    },
+    /// Empties the cells within [`DRAIN_REACH`] of `position` every tick.
+    Drain { position: (u16, u16) },
+}
+
+impl Fixture {
+    pub fn position(&self) -> (u16, u16) {
+        match *self {
+            Self::Source { position, .. } | Self::Drain { position } => position,
+        }
+    }
+
+    pub fn set_position(&mut self, to: (u16, u16)) {
+        match self {
+            Self::Source { position, .. } | Self::Drain { position } => *position = to,
+        }
+    }
+}
+
+/// Outlines every fixture, white for sources and black for drains.
+pub fn draw_markers(
+    buffer: &mut [u32],
+    temporaries: &mut Vec<(usize, u32)>,
+    (width, height): (u16, u16),
+    fixtures: &[Fixture],
+) {
+    for fixture in fixtures {
+        let (color, reach) = match fixture {
+            Fixture::Source { .. } => (SOURCE_MARKER, 1),
+            Fixture::Drain { .. } => (DRAIN_MARKER, DRAIN_REACH as i32 + 1),
+        };
+
+        let (x, y) = fixture.position();
+        for dy in -reach..=reach {
+            for dx in -reach..=reach {
+                if dx.abs() != reach && dy.abs() != reach {
+                    continue;
+                }
+
+                let (x, y) = (x as i32 + dx, y as i32 + dy);
+                if (0..width as i32).contains(&x) && (0..height as i32).contains(&y) {
+                    overlay(buffer, temporaries, (y * width as i32 + x) as usize, color);
+                }
+            }
+        }
+    }
+}
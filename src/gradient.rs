@@ -7,17 +7,36 @@ pub type Steps = NonZeroU16;
 pub(crate) struct Gradient {
     start: (u8, u8, u8),
     end: (u8, u8, u8),
-    step: (f32, f32, f32),
-    cur_step: (f32, f32, f32),
+    steps: Steps,
+    position: u16,
     up: bool,
 }
 
-fn calculate_step(start: (u8, u8, u8), end: (u8, u8, u8), steps: Steps) -> (f32, f32, f32) {
-    (
-        ((end.0 - start.0) as f32 / steps.get() as f32),
-        ((end.1 - start.1) as f32 / steps.get() as f32),
-        ((end.2 - start.2) as f32 / steps.get() as f32),
-    )
+fn lerp(start: u8, end: u8, position: u16, steps: Steps) -> u8 {
+    let t = position as f32 / steps.get() as f32;
+    (start as f32 + (end as f32 - start as f32) * t) as u8
+}
+
+pub fn split_rgb(rgb: u32) -> (u8, u8, u8) {
+    ((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+}
+
+/// Average of two `0xRRGGBB` colors.
+pub fn mix(a: u32, b: u32) -> u32 {
+    let (a, b) = (split_rgb(a), split_rgb(b));
+    let avg = |x: u8, y: u8| ((x as u32 + y as u32) / 2) as u8;
+    join_rgb((avg(a.0, b.0), avg(a.1, b.1), avg(a.2, b.2)))
+}
+
+/// `a` moved `t` of the way towards `b`, `t` in `0.0..=1.0`.
+pub fn blend(a: u32, b: u32, t: f32) -> u32 {
+    let (a, b) = (split_rgb(a), split_rgb(b));
+    let towards = |x: u8, y: u8| (x as f32 + (y as f32 - x as f32) * t).round() as u8;
+    join_rgb((towards(a.0, b.0), towards(a.1, b.1), towards(a.2, b.2)))
+}
+
+fn join_rgb(color: (u8, u8, u8)) -> u32 {
+    ((color.0 as u32) << 16) | ((color.1 as u32) << 8) | color.2 as u32
 }
 
 impl Gradient {
@@ -28,57 +47,67 @@ impl Gradient {
         let start = (n1.0.min(n2.0), n1.1.min(n2.1), n1.2.min(n2.2));
 
         let end = (n1.0.max(n2.0), n1.1.max(n2.1), n1.2.max(n2.2));
-        let cur_step = (start.0 as f32, start.1 as f32, start.2 as f32);
 
-        let step = calculate_step(start, end, steps);
         Self {
             start,
             end,
-            step,
-            cur_step,
+            steps,
+            position: 0,
+            up: true,
+        }
+    }
+
+    /// Gradient going from exactly `start` to `end` and back, both `0xRRGGBB`.
+    pub fn between(start: u32, end: u32, steps: Steps) -> Self {
+        Self {
+            start: split_rgb(start),
+            end: split_rgb(end),
+            steps,
+            position: 0,
             up: true,
         }
     }
 
+    /// The colors at both ends, `0xRRGGBB`.
+    pub fn ends(&self) -> (u32, u32) {
+        (join_rgb(self.start), join_rgb(self.end))
+    }
+
     pub fn peek_color(&self) -> u32 {
         let color = (
-            self.cur_step.0 as u8,
-            self.cur_step.1 as u8,
-            self.cur_step.2 as u8,
+            lerp(self.start.0, self.end.0, self.position, self.steps),
+            lerp(self.start.1, self.end.1, self.position, self.steps),
+            lerp(self.start.2, self.end.2, self.position, self.steps),
         );
 
-        ((color.0 as u32) << 16) | ((color.1 as u32) << 8) | color.2 as u32
+        join_rgb(color)
+    }
+
+    /// The current color followed by the colors `spacing` steps apart after it.
+    pub fn upcoming(&self, count: usize, spacing: usize) -> Vec<u32> {
+        let mut gradient = self.clone();
+        (0..count)
+            .map(|_| {
+                let color = gradient.peek_color();
+                for _ in 0..spacing {
+                    gradient.next_color();
+                }
+                color
+            })
+            .collect()
     }
 
     pub fn next_color(&mut self) -> u32 {
-        let mut color = (
-            self.cur_step.0 as u8,
-            self.cur_step.1 as u8,
-            self.cur_step.2 as u8,
-        );
+        let color = self.peek_color();
 
-        if self.up && color >= self.end {
-            color = self.end;
-            self.cur_step = (self.end.0 as f32, self.end.1 as f32, self.end.2 as f32);
-            self.up = false;
-        } else if !self.up && color <= self.start {
-            color = self.start;
-            self.cur_step = (
-                self.start.0 as f32,
-                self.start.1 as f32,
-                self.start.2 as f32,
-            );
-            self.up = true;
-        } else if self.up {
-            self.cur_step.0 += self.step.0;
-            self.cur_step.1 += self.step.1;
-            self.cur_step.2 += self.step.2;
+        if self.up {
+            self.position += 1;
+            self.up = self.position < self.steps.get();
         } else {
-            self.cur_step.0 -= self.step.0;
-            self.cur_step.1 -= self.step.1;
-            self.cur_step.2 -= self.step.2;
+            self.position -= 1;
+            self.up = self.position == 0;
         }
 
-        ((color.0 as u32) << 16) | ((color.1 as u32) << 8) | color.2 as u32
+        color
     }
 }
@@ -1,26 +1,52 @@
-use fastrand::Rng;
-
-use crate::{radii::RadiusId, resize, DEFAULT_HEIGHT, DEFAULT_WIDTH};
+use std::collections::{HashMap, HashSet};
 
-pub fn circle_offsets(radius: f64) -> impl Iterator<Item = (isize, isize)> {
-    let radius_ceil = radius.ceil() as isize;
-    let sq_radius = radius * radius;
+use fastrand::Rng;
 
-    (-radius_ceil + 1..radius_ceil)
-        .flat_map(move |y| (-radius_ceil + 1..radius_ceil).map(move |x| (x, y)))
-        .filter(move |(x, y)| {
-            let x = *x as f64;
-            let y = *y as f64;
-            x * x + y * y <= sq_radius
-        })
-}
+use crate::{
+    body::{Body, BODY_GRAVITY},
+    brush::Brush,
+    fixture::{Fixture, DRAIN_REACH},
+    gradient::blend,
+    image::{alpha, channel_distance, Image},
+    material::{Material, MaterialId, Movement, BLOCK, MAX_SLIDE, SAND},
+    reaction::Reactions,
+    resize, DEFAULT_HEIGHT, DEFAULT_WIDTH,
+};
 
 #[derive(Clone)]
 pub struct Grid {
     width: u16,
     height: u16,
-    radius: f64,
+    brush: Brush,
+    stamps: u32,
+    wind: f32,
     pub colors: Vec<u32>,
+    /// Material of every cell, meaningless for empty ones.
+    material_ids: Vec<MaterialId>,
+    materials: Vec<Material>,
+    reactions: Reactions,
+    /// Rows per tick every cell is falling at, only used with gravity.
+    velocities: Vec<f32>,
+    /// Ticks left for cells of materials with a lifetime.
+    lifetimes: Vec<u16>,
+    /// Colors cells were given when filled, which aging cells blend away from.
+    base_colors: Vec<u32>,
+    /// Temperature of every cell, empty ones included.
+    temperatures: Vec<f32>,
+    /// Scratch space for [`Grid::diffuse`].
+    next_temperatures: Vec<f32>,
+    /// Whether any temperature is off [`AMBIENT`] or any cell gives off heat,
+    /// [`Grid::diffuse`] skips the grid while nothing is.
+    warm: bool,
+    /// Value of `tick` when every cell last moved or changed material, so
+    /// cells moving up or along their row aren't updated twice in one tick.
+    last_moved: Vec<u32>,
+    tick: u32,
+    physics: Physics,
+    edges: Edges,
+    /// Sources and drains placed in the scene.
+    fixtures: Vec<Fixture>,
+    bodies: Vec<Body>,
     #[cfg(debug_assertions)]
     pub checked: Vec<u32>,
     rng: Rng,
@@ -36,17 +62,155 @@ enum Direction {
     Down = 1,
     DownLeft = 2,
     DownRight = 3,
+    Sideways = 4,
+    Up = 5,
+    /// Stayed put by chance although it could have moved, so it's still
+    /// updated next tick.
+    Held = 6,
 }
 
 pub const EMPTY: u32 = 0xFFE0FFFE;
 
+/// Temperature everything cools or warms back to.
+pub const AMBIENT: f32 = 20.0;
+
+/// Smallest temperature change that shows in the heat view, slower drift
+/// doesn't keep the grid updating.
+const VISIBLE_DRIFT: f32 = 0.5;
+
+/// How grains speed up while falling.
+#[derive(Clone, Copy)]
+pub struct Physics {
+    /// Rows per tick added to a falling grain's speed every tick, zero makes
+    /// every grain fall one row per tick.
+    pub gravity: f32,
+    /// Fastest a grain can fall in rows per tick.
+    pub terminal_velocity: f32,
+    /// Speed from which landing grains scatter sideways, by up to half of it.
+    pub splash: f32,
+    /// Speed from which landing bodies break into grains.
+    pub shatter: f32,
+    /// Part of the way a cell's temperature moves towards its neighbours'
+    /// every tick, zero turns temperature off.
+    pub diffusion: f32,
+    /// Part of the way every temperature moves back to [`AMBIENT`] every tick.
+    pub cooling: f32,
+}
+
+impl Default for Physics {
+    fn default() -> Self {
+        Self {
+            gravity: 0.0,
+            terminal_velocity: 8.0,
+            splash: 4.0,
+            shatter: 6.0,
+            diffusion: 0.2,
+            cooling: 0.002,
+        }
+    }
+}
+
+/// What happens to cells at one side of the grid.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    /// Cells stop, the way they do against walls.
+    Solid,
+    /// Cells carry on from the opposite side.
+    Wrap,
+    /// Cells heading out through the outermost row or column fall off the grid.
+    Open,
+}
+
+impl Edge {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "solid" => Some(Self::Solid),
+            "wrap" => Some(Self::Wrap),
+            "open" => Some(Self::Open),
+            _ => None,
+        }
+    }
+}
+
+/// The [`Edge`] of every side of the grid.
+#[derive(Clone, Copy)]
+pub struct Edges {
+    pub left: Edge,
+    pub right: Edge,
+    pub top: Edge,
+    pub bottom: Edge,
+}
+
+impl Default for Edges {
+    fn default() -> Self {
+        Self {
+            left: Edge::Solid,
+            right: Edge::Solid,
+            top: Edge::Solid,
+            bottom: Edge::Solid,
+        }
+    }
+}
+
+/// Offsets of the eight cells around a cell.
+const NEIGHBOURS: [(i16, i16); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// Columns the slope is measured over by [`Grid::angle_of_repose`], single
+/// columns only ever differ by whole cells.
+const REPOSE_SPAN: usize = 8;
+
+/// Summary of the grid for displaying, see [`Grid::stats`].
+#[derive(Clone, Default)]
+pub struct Stats {
+    pub grains: usize,
+    pub colors: usize,
+    /// Cells of every material, indexed by [`MaterialId`].
+    pub materials: Vec<usize>,
+    /// Top left and bottom right corner of the occupied cells, inclusive.
+    pub bounds: Option<((u16, u16), (u16, u16))>,
+    pub settled: bool,
+    /// Angle of repose in degrees.
+    pub repose: Option<f32>,
+}
+
 impl Grid {
-    pub fn new() -> Self {
+    pub fn new(
+        materials: Vec<Material>,
+        reactions: Reactions,
+        physics: Physics,
+        edges: Edges,
+    ) -> Self {
         Self {
             width: DEFAULT_WIDTH as u16,
             height: DEFAULT_HEIGHT as u16,
-            radius: RadiusId::default().get(),
+            brush: Brush::Circle,
+            stamps: 0,
+            wind: 0.0,
             colors: vec![EMPTY; DEFAULT_WIDTH * DEFAULT_HEIGHT],
+            material_ids: vec![SAND; DEFAULT_WIDTH * DEFAULT_HEIGHT],
+            materials,
+            reactions,
+            velocities: vec![0.0; DEFAULT_WIDTH * DEFAULT_HEIGHT],
+            lifetimes: vec![0; DEFAULT_WIDTH * DEFAULT_HEIGHT],
+            base_colors: vec![EMPTY; DEFAULT_WIDTH * DEFAULT_HEIGHT],
+            temperatures: vec![AMBIENT; DEFAULT_WIDTH * DEFAULT_HEIGHT],
+            next_temperatures: vec![AMBIENT; DEFAULT_WIDTH * DEFAULT_HEIGHT],
+            warm: false,
+            last_moved: vec![0; DEFAULT_WIDTH * DEFAULT_HEIGHT],
+            tick: 0,
+            physics,
+            edges,
+            fixtures: vec![],
+            bodies: vec![],
             rng: Rng::new(),
             #[cfg(debug_assertions)]
             checked: vec![EMPTY; DEFAULT_WIDTH * DEFAULT_HEIGHT],
@@ -58,11 +222,20 @@ impl Grid {
     }
 
     pub fn update(&mut self) -> bool {
-        let mut updated = false;
+        self.tick = self.tick.wrapping_add(1);
 
         #[cfg(debug_assertions)]
         self.checked.fill(EMPTY);
+        let mut updated = self.run_fixtures();
+        updated |= self.drop_bodies();
+        updated |= self.diffuse();
+        if self.left_skip > self.right_skip {
+            // The last tick found no cells, scanning would wrap around.
+            return updated;
+        }
+
         let mut lowest_row = 0;
+        let mut highest_row = self.height - 1;
         let mut most_left = self.width - 1;
         let mut most_right = 0;
         for row in (self.highest_row..=self.lowest_row).rev() {
@@ -84,21 +257,42 @@ impl Grid {
             loop {
                 let i = row as u32 * self.width as u32 + column as u32;
 
-                if !self.is_empty(i) {
-                    let moved = self.update_pixel(i, column);
-                    updated_this_row |= moved != Direction::None;
+                if !self.is_empty(i) && self.last_moved[i as usize] == self.tick {
+                    // Moved up or along the row into here, keep the row around.
+                    updated_this_row = true;
+                    most_left = most_left.min(column);
+                    most_right = most_right.max(column);
+                } else if !self.is_empty(i) && self.is_open_edge(row, self.material(i).movement) {
+                    self.colors[i as usize] = EMPTY;
+                    updated_this_row = true;
+                } else if !self.is_empty(i) {
+                    let reacting = self.react(i, column);
+                    let (moved, to) = self.update_pixel(i, column);
+                    let to_column = (to % self.width as u32) as u16;
+                    if moved != Direction::None || reacting {
+                        updated_this_row = true;
+                        // Fast grains may skip several rows, and wrapping
+                        // ones come out at the top.
+                        let to_row = (to / self.width as u32) as u16;
+                        lowest_row = lowest_row.max(to_row);
+                        highest_row = highest_row.min(to_row);
+                    }
 
-                    if moved == Direction::DownLeft {
-                        // Moved left, column to the left has a pixel
-                        most_left = most_left.min(column - 1);
-                    } else if moved == Direction::DownRight {
-                        // Moved right, column to the right has a pixel
-                        most_right = most_right.max(column + 1);
-                    } else {
-                        // Stayed in this colum.
+                    // The column the pixel ended up in has a pixel now, as
+                    // does the one it left if it swapped places.
+                    most_left = most_left.min(to_column);
+                    most_right = most_right.max(to_column);
+                    if moved != Direction::None {
                         most_left = most_left.min(column);
                         most_right = most_right.max(column);
                     }
+
+                    if reacting {
+                        // Keep aging or reacting even when still, and catch
+                        // the neighbours it may have changed.
+                        most_left = most_left.min(column.saturating_sub(1));
+                        most_right = most_right.max(column + 1);
+                    }
                 }
 
                 if column == limit {
@@ -114,39 +308,468 @@ impl Grid {
                 self.highest_row = self.height.min(self.highest_row + 1);
             } else if updated_this_row {
                 // check row above this one again
-                self.highest_row = self.highest_row.min(row - 1);
+                self.highest_row = self.highest_row.min(row.saturating_sub(1));
                 lowest_row = lowest_row.max(row + 1);
             }
         }
 
-        self.lowest_row = lowest_row.min(self.height - 2);
+        // Rows above the scanned ones may have missed cells moving into
+        // columns that weren't scanned.
+        self.highest_row = self.highest_row.min(highest_row);
+        self.lowest_row = lowest_row.min(self.height - 1);
         self.left_skip = most_left;
         self.right_skip = most_right.min(self.width - 1);
 
         updated
     }
 
-    fn update_pixel(&mut self, i: u32, column: u16) -> Direction {
-        let below = i + self.width as u32;
-        let below_left = below - 1;
-        let below_right = below + 1;
+    fn material(&self, i: u32) -> Material {
+        self.materials[self.material_ids[i as usize] as usize]
+    }
+
+    /// Moves the pixel at `i`, returning how and where it ended up.
+    fn update_pixel(&mut self, i: u32, column: u16) -> (Direction, u32) {
+        if self.colors[i as usize] == EMPTY {
+            return (Direction::None, i);
+        }
+
+        let material = self.material(i);
+        match material.movement {
+            Movement::Solid => (Direction::None, i),
+            Movement::Powder | Movement::Liquid { .. } => self.fall(i, column, &material),
+            Movement::Gas { buoyancy } => self.rise(i, column, buoyancy),
+        }
+    }
+
+    /// Runs the reactions of the cell at `i` with its neighbours and its
+    /// lifetime. Returns whether it changed or could change soon, which
+    /// keeps it updating.
+    fn react(&mut self, i: u32, column: u16) -> bool {
+        let id = self.material_ids[i as usize];
+        let material = self.materials[id as usize];
+        let mut reacting = material.is_aging();
+
+        if !self.reactions.of(id).is_empty() {
+            let row = (i / self.width as u32) as u16;
+            for (dx, dy) in NEIGHBOURS {
+                let x = column.wrapping_add_signed(dx);
+                let y = row.wrapping_add_signed(dy);
+                if x >= self.width || y >= self.height {
+                    continue;
+                }
+
+                let neighbour = y as u32 * self.width as u32 + x as u32;
+                if self.colors[neighbour as usize] == EMPTY {
+                    continue;
+                }
+
+                let with = self.material_ids[neighbour as usize];
+                for r in 0..self.reactions.of(id).len() {
+                    let reaction = self.reactions.of(id)[r];
+                    if reaction.with != with {
+                        continue;
+                    }
+
+                    reacting = true;
+                    // Both cells know the rule, only the one first in the
+                    // grid rolls so the chance holds per tick.
+                    if neighbour < i {
+                        break;
+                    }
+
+                    if self.rng.f32() < reaction.chance {
+                        self.become_(neighbour, reaction.other_into);
+                        self.become_(i, reaction.into);
+                        return true;
+                    }
+                }
+            }
+        }
+
+        if material.flicker && reacting {
+            let color = self.rng.choice(material.colors).copied().unwrap();
+            self.colors[i as usize] = color;
+            self.base_colors[i as usize] = color;
+        }
+
+        if let Some((shortest, _)) = material.lifetime {
+            let remaining = &mut self.lifetimes[i as usize];
+            *remaining = remaining.saturating_sub(1);
+            let remaining = *remaining;
+
+            if remaining == 0 {
+                self.become_(i, material.decays_to);
+                return true;
+            }
+
+            if let Some(target) = material.ages_to {
+                // Over the shortest lifetime, so every cell gets all the way.
+                let age = 1.0 - remaining.min(shortest) as f32 / shortest.max(1) as f32;
+                self.colors[i as usize] = blend(self.base_colors[i as usize], target, age);
+            }
+        }
+        reacting
+    }
+
+    /// Spreads heat between neighbouring cells, with hot materials heating
+    /// what's around them, then changes the cells that got hot or cold enough
+    /// into other materials. Returns whether any temperature changed visibly.
+    fn diffuse(&mut self) -> bool {
+        let rate = self.physics.diffusion;
+        if rate == 0.0 || !self.warm {
+            return false;
+        }
+
+        let (width, height) = (self.width as usize, self.height as usize);
+        let mut changed = false;
+        let mut warm = false;
+        for y in 0..height {
+            for x in 0..width {
+                let i = y * width + x;
+                let t = self.temperatures[i];
+                let neighbours = [
+                    if x > 0 { self.temperatures[i - 1] } else { t },
+                    if x + 1 < width {
+                        self.temperatures[i + 1]
+                    } else {
+                        t
+                    },
+                    if y > 0 {
+                        self.temperatures[i - width]
+                    } else {
+                        t
+                    },
+                    if y + 1 < height {
+                        self.temperatures[i + width]
+                    } else {
+                        t
+                    },
+                ];
+                let average = neighbours.iter().sum::<f32>() / 4.0;
+
+                let mut next = t + rate * (average - t);
+                next += self.physics.cooling * (AMBIENT - next);
+                if self.colors[i] != EMPTY {
+                    if let Some(heat) = self.materials[self.material_ids[i] as usize].heat {
+                        next = heat;
+                        warm = true;
+                    }
+                }
+
+                changed |= (next - t).abs() > VISIBLE_DRIFT;
+                warm |= (next - AMBIENT).abs() > 0.01;
+                self.next_temperatures[i] = next;
+            }
+        }
+        std::mem::swap(&mut self.temperatures, &mut self.next_temperatures);
+        self.warm = warm;
+
+        for i in 0..width * height {
+            if self.colors[i] == EMPTY {
+                continue;
+            }
+
+            let material = &self.materials[self.material_ids[i] as usize];
+            let t = self.temperatures[i];
+            let into = match (material.melts, material.freezes) {
+                (Some((above, into)), _) if t > above => into,
+                (_, Some((below, into))) if t < below => into,
+                _ => continue,
+            };
+
+            self.become_(i as u32, Some(into));
+            self.wake((i % width) as u16, (i / width) as u16);
+            changed = true;
+        }
+        changed
+    }
+
+    pub fn temperatures(&self) -> &[f32] {
+        &self.temperatures
+    }
+
+    /// Moves the gas cell at `i` up with `buoyancy` chance, drifting sideways
+    /// and spreading out under whatever stops it.
+    fn rise(&mut self, i: u32, column: u16, buoyancy: f32) -> (Direction, u32) {
+        let row_start = i - column as u32;
+        let step = self.rng.i16(-1..=1);
+        let drift = match self.beside(column, step) {
+            Some(drift) => drift,
+            None if self.leaves(column, step) => return self.fall_off(i, Direction::Sideways),
+            None => column,
+        };
+
+        if let Some(above) = self.above(row_start).filter(|_| self.rng.f32() < buoyancy) {
+            for x in [drift, column] {
+                if self.is_empty(above + x as u32) {
+                    self.move_(i, above + x as u32);
+                    return (Direction::Up, above + x as u32);
+                }
+            }
+        }
+
+        if drift != column && self.is_empty(row_start + drift as u32) {
+            self.move_(i, row_start + drift as u32);
+            return (Direction::Sideways, row_start + drift as u32);
+        }
+        (Direction::None, i)
+    }
+
+    /// Lets the grain at `i` fall, slide or scatter.
+    fn fall(&mut self, i: u32, column: u16, material: &Material) -> (Direction, u32) {
+        let Some(below_row) = self.below(i - column as u32) else {
+            self.velocities[i as usize] = 0.0;
+            return (Direction::None, i);
+        };
+        let below = below_row + column as u32;
+        let below_left = self.beside(column, -1).map(|x| below_row + x as u32);
+        let below_right = self.beside(column, 1).map(|x| below_row + x as u32);
 
         // If there are no pixels below, move it down.
         if self.is_empty(below) {
+            let distance = self.accelerate(i);
+
+            // Falling grains may get blown diagonally instead.
+            if self.wind != 0.0 && self.rng.f32() < self.wind.abs() {
+                let (target, direction) = if self.wind < 0.0 {
+                    (below_left, Direction::DownLeft)
+                } else {
+                    (below_right, Direction::DownRight)
+                };
+
+                let step = if self.wind < 0.0 { -1 } else { 1 };
+                match target {
+                    Some(target) if self.is_empty(target) => {
+                        self.move_(i, target);
+                        return (direction, target);
+                    }
+                    None if self.leaves(column, step) => return self.fall_off(i, direction),
+                    _ => {}
+                }
+            }
+
+            // Walk down the column, stopping on top of whatever is in the way.
+            let mut to = below;
+            for _ in 1..distance {
+                let next = to + self.width as u32;
+                if next as usize >= self.colors.len() || !self.is_empty(next) {
+                    break;
+                }
+                to = next;
+            }
+
+            self.move_(i, to);
+            return (Direction::Down, to);
+        }
+
+        if self.can_enter(below, material.density) {
+            self.velocities[i as usize] = 0.0;
             self.move_(i, below);
-            return Direction::Down;
-        } else if column != 0 && self.is_empty(below_left) {
-            self.move_(i, below_left);
-            return Direction::DownLeft;
-        } else if column != self.width - 1 && self.is_empty(below_right) {
-            self.move_(i, below_right);
-            return Direction::DownRight;
+            return (Direction::Down, below);
+        }
+
+        // Landed, unless on a grain that is still falling. Grains that land
+        // fast enough scatter sideways on impact.
+        let speed = std::mem::take(&mut self.velocities[i as usize]);
+        let below_speed = self.velocities[below as usize];
+        if below_speed > 0.0 {
+            self.velocities[i as usize] = speed.min(below_speed);
+        } else if self.physics.gravity > 0.0 && speed >= self.physics.splash {
+            if let Some(to) = self.scatter(i, column, speed) {
+                self.move_(i, to);
+                return (Direction::Sideways, to);
+            }
+        }
+
+        // Past an open side there's always room, `None` takes the grain off
+        // the grid.
+        let density = material.density;
+        let mut diagonal = |target: Option<u32>, step, direction| match target {
+            Some(target) => self
+                .can_enter(target, density)
+                .then_some((Some(target), direction)),
+            None => self.leaves(column, step).then_some((None, direction)),
+        };
+        let target = diagonal(below_left, -1, Direction::DownLeft)
+            .or_else(|| diagonal(below_right, 1, Direction::DownRight));
+
+        if let Some((target, direction)) = target {
+            // Sticky grains that stay put may get buried before they get
+            // another chance, which is what makes their piles steeper.
+            if self.rng.f32() < material.stickiness {
+                return (Direction::Held, i);
+            }
+
+            let Some(target) = target else {
+                return self.fall_off(i, direction);
+            };
+            self.move_(i, target);
+            return (direction, target);
+        }
+
+        if material.slide > 0.0 {
+            let row = i - column as u32;
+            let step = if self.rng.bool() { 1 } else { -1 };
+            for step in [step, -step] {
+                if let Some(to) = self.slide(row, column, step, material.slide) {
+                    let to = below_row + to as u32;
+                    self.move_(i, to);
+                    let direction = if step < 0 {
+                        Direction::DownLeft
+                    } else {
+                        Direction::DownRight
+                    };
+                    return (direction, to);
+                }
+            }
+        }
+
+        if let Movement::Liquid { flow } = material.movement {
+            let row = i - column as u32;
+            let step = if self.rng.bool() { 1 } else { -1 };
+            for step in [step, -step] {
+                let mut to = column;
+                for _ in 0..flow {
+                    let Some(next) = self.beside(to, step) else {
+                        if self.leaves(to, step) {
+                            return self.fall_off(i, Direction::Sideways);
+                        }
+                        break;
+                    };
+
+                    if self.is_empty(row + next as u32) {
+                        to = next;
+                        continue;
+                    }
+
+                    // Pushing into a lighter liquid levels the two out.
+                    if to == column && self.can_enter(row + next as u32, material.density) {
+                        to = next;
+                    }
+                    break;
+                }
+
+                if to != column {
+                    self.move_(i, row + to as u32);
+                    return (Direction::Sideways, row + to as u32);
+                }
+            }
+        }
+        (Direction::None, i)
+    }
+
+    /// Speeds up the falling grain at `i`, returning how many rows it falls
+    /// this tick. Always one without gravity.
+    fn accelerate(&mut self, i: u32) -> u16 {
+        if self.physics.gravity == 0.0 {
+            return 1;
+        }
+
+        let velocity = &mut self.velocities[i as usize];
+        *velocity = (*velocity + self.physics.gravity).min(self.physics.terminal_velocity);
+        (*velocity as u16).max(1)
+    }
+
+    /// Where a grain at `i` that hit the ground at `speed` bounces to along
+    /// its row, `None` if it has no room to.
+    fn scatter(&mut self, i: u32, column: u16, speed: f32) -> Option<u32> {
+        let reach = (speed / 2.0) as u16;
+        let step = if self.rng.bool() { 1 } else { -1 };
+
+        let mut to = column;
+        for _ in 0..self.rng.u16(1..=reach.max(1)) {
+            match self.beside(to, step) {
+                Some(next) if self.is_empty(i - column as u32 + next as u32) => to = next,
+                _ => break,
+            }
+        }
+        (to != column).then(|| i - column as u32 + to as u32)
+    }
+
+    /// Column a grain at `column` of the row starting at `row` can slide to
+    /// along its row and drop down one, rolling `chance` for every cell it
+    /// passes. `None` if it is blocked or runs out of luck first.
+    fn slide(&mut self, row: u32, column: u16, step: i16, chance: f32) -> Option<u16> {
+        let below_row = self.below(row)?;
+        let mut to = column;
+        for _ in 0..MAX_SLIDE {
+            to = self.beside(to, step)?;
+            if !self.is_empty(row + to as u32) || self.rng.f32() >= chance {
+                return None;
+            }
+
+            if self.is_empty(below_row + to as u32) {
+                return Some(to);
+            }
+        }
+        None
+    }
+
+    /// The column `step` away from `column`, wrapping around the sides that
+    /// wrap. `None` past a side that doesn't.
+    fn beside(&self, column: u16, step: i16) -> Option<u16> {
+        let next = column as i32 + step as i32;
+        let width = self.width as i32;
+        match next {
+            _ if (0..width).contains(&next) => Some(next as u16),
+            _ if next < 0 && self.edges.left == Edge::Wrap => Some((next + width) as u16),
+            _ if next >= width && self.edges.right == Edge::Wrap => Some((next - width) as u16),
+            _ => None,
+        }
+    }
+
+    /// Whether stepping `step` from `column` goes past a side that's open.
+    fn leaves(&self, column: u16, step: i16) -> bool {
+        let next = column as i32 + step as i32;
+        (next < 0 && self.edges.left == Edge::Open)
+            || (next >= self.width as i32 && self.edges.right == Edge::Open)
+    }
+
+    /// Removes the cell at `i`, which moved past an open side.
+    fn fall_off(&mut self, i: u32, direction: Direction) -> (Direction, u32) {
+        self.colors[i as usize] = EMPTY;
+        (direction, i)
+    }
+
+    /// Start of the row below the one starting at `row`, the top one if the
+    /// bottom wraps.
+    fn below(&self, row: u32) -> Option<u32> {
+        let next = row + self.width as u32;
+        if (next as usize) < self.colors.len() {
+            Some(next)
+        } else {
+            (self.edges.bottom == Edge::Wrap).then_some(0)
+        }
+    }
+
+    /// Start of the row above the one starting at `row`, the bottom one if
+    /// the top wraps.
+    fn above(&self, row: u32) -> Option<u32> {
+        if row != 0 {
+            Some(row - self.width as u32)
+        } else {
+            (self.edges.top == Edge::Wrap).then(|| (self.height as u32 - 1) * self.width as u32)
         }
-        Direction::None
+    }
+
+    /// Whether a cell of `movement` in `row` is about to fall off the top or
+    /// bottom of the grid. Cells only leave through the edges they move
+    /// towards, so solids stay put and grains spawned along an open top fall
+    /// back in. Open sides are left by moving past them, see [`Grid::leaves`].
+    fn is_open_edge(&self, row: u16, movement: Movement) -> bool {
+        let rises = match movement {
+            Movement::Solid => return false,
+            Movement::Gas { .. } => true,
+            Movement::Powder | Movement::Liquid { .. } => false,
+        };
+        (row == 0 && rises && self.edges.top == Edge::Open)
+            || (row == self.height - 1 && !rises && self.edges.bottom == Edge::Open)
     }
 
     pub fn clear(&mut self) {
         self.colors.fill(EMPTY);
+        self.temperatures.fill(AMBIENT);
+        self.warm = false;
         #[cfg(debug_assertions)]
         self.checked.fill(EMPTY);
         self.highest_row = self.height - 1;
@@ -155,15 +778,68 @@ impl Grid {
         self.right_skip = 0;
     }
 
+    /// Moves the cell at `a` to `b`, whatever was in `b` takes its place.
     fn move_(&mut self, a: u32, b: u32) {
-        self.colors[b as usize] = self.colors[a as usize];
-        self.colors[a as usize] = EMPTY;
+        let (a, b) = (a as usize, b as usize);
+        self.colors.swap(a, b);
+        self.material_ids.swap(a, b);
+        self.lifetimes.swap(a, b);
+        self.base_colors.swap(a, b);
+        self.temperatures.swap(a, b);
+        // Displaced cells start over at rest.
+        self.velocities[b] = std::mem::take(&mut self.velocities[a]);
+        self.last_moved[a] = self.tick;
+        self.last_moved[b] = self.tick;
+    }
+
+    /// Whether a cell of `density` can move into `target`, because it's empty
+    /// or holds a lighter liquid or gas. Sinking is slower the closer the
+    /// densities are.
+    fn can_enter(&mut self, target: u32, density: f32) -> bool {
+        if self.is_empty(target) {
+            return true;
+        }
+
+        let other = self.material(target);
+        other.is_fluid()
+            && other.density < density
+            && self.rng.f32() < (density - other.density) / density
     }
 
-    pub fn set_pixel(&mut self, a: u32, color: u32) {
+    /// Makes `a` a fresh cell of `material`, drawn with `color` unless the
+    /// material has colors of its own.
+    pub fn set_pixel(&mut self, a: u32, color: u32, material: MaterialId) {
         assert!(self.is_empty(a));
 
-        self.colors[a as usize] = color;
+        self.fill(a, color, material);
+        let material = self.materials[material as usize];
+        if let Some(temperature) = material.heat.or(material.temperature) {
+            self.temperatures[a as usize] = temperature;
+        }
+    }
+
+    fn fill(&mut self, a: u32, color: u32, id: MaterialId) {
+        let material = self.materials[id as usize];
+        let a = a as usize;
+        self.colors[a] = self.rng.choice(material.colors).copied().unwrap_or(color);
+        self.base_colors[a] = self.colors[a];
+        self.material_ids[a] = id;
+        self.velocities[a] = 0.0;
+        self.warm |= material.heat.is_some() || material.temperature.is_some();
+        self.lifetimes[a] = material
+            .lifetime
+            .map_or(0, |(shortest, longest)| self.rng.u16(shortest..=longest));
+    }
+
+    /// Turns the cell at `a` into `id`, or empties it for `None`. It waits
+    /// until the next tick to update.
+    fn become_(&mut self, a: u32, id: Option<MaterialId>) {
+        match id {
+            Some(id) if id == self.material_ids[a as usize] => return,
+            Some(id) => self.fill(a, self.colors[a as usize], id),
+            None => self.colors[a as usize] = EMPTY,
+        }
+        self.last_moved[a as usize] = self.tick;
     }
 
     pub fn is_empty(&mut self, a: u32) -> bool {
@@ -177,20 +853,25 @@ impl Grid {
         ret
     }
 
-    pub fn spawn(&mut self, mouse_pos: (u16, u16), color: u32) -> bool {
+    pub fn spawn(
+        &mut self,
+        mouse_pos: (u16, u16),
+        color: u32,
+        radius: f64,
+        material: MaterialId,
+    ) -> bool {
         let mut placed_pixels = false;
-        for (dx, dy) in circle_offsets(self.radius) {
+        self.stamps = self.stamps.wrapping_add(1);
+        let brush = self.brush.clone();
+        for (dx, dy) in brush.offsets(radius, self.stamps) {
             let x = mouse_pos.0 as isize + dx;
             let y = mouse_pos.1 as isize + dy;
 
             if x >= 0 && x < self.width as isize && y >= 0 && y < self.height as isize {
-                self.highest_row = self.highest_row.min(y as u16);
-                self.lowest_row = self.lowest_row.max(y as u16).min(self.height - 2);
-                self.left_skip = self.left_skip.min(x as u16);
-                self.right_skip = self.right_skip.max(x as u16).min(self.width - 1);
+                self.wake(x as u16, y as u16);
                 let index = (y * self.width as isize + x) as u32;
                 if self.is_empty(index) {
-                    self.set_pixel(index, color);
+                    self.set_pixel(index, color, material);
                     placed_pixels = true;
                 }
             }
@@ -198,24 +879,683 @@ impl Grid {
         placed_pixels
     }
 
+    /// Makes the next update look at the cell at `x`, `y`.
+    fn wake(&mut self, x: u16, y: u16) {
+        self.highest_row = self.highest_row.min(y);
+        self.lowest_row = self.lowest_row.max(y);
+        self.left_skip = self.left_skip.min(x);
+        self.right_skip = self.right_skip.max(x).min(self.width - 1);
+    }
+
+    pub fn fixtures(&self) -> &[Fixture] {
+        &self.fixtures
+    }
+
+    pub fn place(&mut self, fixture: Fixture) {
+        let (x, y) = fixture.position();
+        if x < self.width && y < self.height {
+            self.fixtures.push(fixture);
+        }
+    }
+
+    /// Removes every fixture within `reach` cells of `position`.
+    pub fn remove_fixtures(&mut self, (x, y): (u16, u16), reach: u16) {
+        self.fixtures
+            .retain(|f| f.position().0.abs_diff(x) > reach || f.position().1.abs_diff(y) > reach);
+    }
+
+    /// Lets every source spawn and every drain empty its cells, returning
+    /// whether they changed anything.
+    fn run_fixtures(&mut self) -> bool {
+        let mut changed = false;
+        for f in 0..self.fixtures.len() {
+            match self.fixtures[f] {
+                Fixture::Source {
+                    position: (x, y),
+                    material,
+                    color,
+                } => {
+                    let i = y as u32 * self.width as u32 + x as u32;
+                    if self.is_empty(i) {
+                        self.set_pixel(i, color, material);
+                        self.wake(x, y);
+                        changed = true;
+                    }
+                }
+                Fixture::Drain { position: (x, y) } => {
+                    let (left, top) =
+                        (x.saturating_sub(DRAIN_REACH), y.saturating_sub(DRAIN_REACH));
+                    let right = (x + DRAIN_REACH).min(self.width - 1);
+                    let bottom = (y + DRAIN_REACH).min(self.height - 1);
+                    let mut emptied = false;
+                    for y in top..=bottom {
+                        for x in left..=right {
+                            let i = y as u32 * self.width as u32 + x as u32;
+                            if !self.is_empty(i) {
+                                self.colors[i as usize] = EMPTY;
+                                emptied = true;
+                            }
+                        }
+                    }
+
+                    changed |= emptied;
+                    if emptied {
+                        // Whatever rested on the emptied cells can fall in.
+                        self.wake(left.saturating_sub(1), top.saturating_sub(1));
+                        self.wake((right + 1).min(self.width - 1), bottom);
+                    }
+                }
+            }
+        }
+        changed
+    }
+
+    /// Turns the empty cells a stamp of the brush at `position` covers into a
+    /// body, drawn in the bitmap's colors or `color`.
+    pub fn drop_body(&mut self, position: (u16, u16), color: u32, radius: f64) -> bool {
+        self.stamps = self.stamps.wrapping_add(1);
+        let brush = self.brush.clone();
+        let mut cells = vec![];
+        for (dx, dy) in brush.offsets(radius, self.stamps) {
+            let x = position.0 as isize + dx;
+            let y = position.1 as isize + dy;
+
+            if x >= 0 && x < self.width as isize && y >= 0 && y < self.height as isize {
+                let (x, y) = (x as u16, y as u16);
+                let index = y as u32 * self.width as u32 + x as u32;
+                if self.is_empty(index) {
+                    let color = brush.color_at(dx, dy, radius).unwrap_or(color);
+                    self.set_pixel(index, color, BLOCK);
+                    self.wake(x, y);
+                    cells.push((x, y));
+                }
+            }
+        }
+
+        let placed = !cells.is_empty();
+        if placed {
+            // Moving the lowest cells first keeps the way clear for the rest.
+            cells.sort_by_key(|&(_, y)| std::cmp::Reverse(y));
+            self.bodies.push(Body {
+                cells,
+                velocity: 0.0,
+            });
+        }
+        placed
+    }
+
+    /// Moves every body down as far as its speed takes it. Bodies that land
+    /// become plain cells, breaking into grains if they land hard enough.
+    /// Returns whether any moved.
+    fn drop_bodies(&mut self) -> bool {
+        let mut moved = false;
+        let mut bodies = std::mem::take(&mut self.bodies);
+        bodies.retain_mut(|body| {
+            // Cells that reacted, drained or were cleared away are gone.
+            body.cells.retain(|&(x, y)| {
+                let i = y as u32 * self.width as u32 + x as u32;
+                !self.is_empty(i) && self.material_ids[i as usize] == BLOCK
+            });
+            if body.cells.is_empty() {
+                return false;
+            }
+
+            let gravity = if self.physics.gravity > 0.0 {
+                self.physics.gravity
+            } else {
+                BODY_GRAVITY
+            };
+            body.velocity = (body.velocity + gravity).min(self.physics.terminal_velocity);
+
+            let speed = (body.velocity as u16).max(1);
+            let own: HashSet<_> = body.cells.iter().copied().collect();
+            let mut rows = 0;
+            while rows < speed && self.body_fits(body, &own, rows + 1) {
+                rows += 1;
+            }
+
+            let landed = rows < speed;
+            let shatters = landed && body.velocity >= self.physics.shatter;
+            for (x, y) in &mut body.cells {
+                let i = *y as u32 * self.width as u32 + *x as u32;
+                let to = i + rows as u32 * self.width as u32;
+                if rows > 0 {
+                    self.move_(i, to);
+                    self.wake(*x, *y);
+                }
+                *y += rows;
+                if shatters {
+                    self.become_(to, Some(SAND));
+                }
+                self.wake(*x, *y);
+            }
+
+            moved |= rows > 0 || shatters;
+            !landed
+        });
+        self.bodies = bodies;
+        moved
+    }
+
+    /// Whether every cell of `body` has room `rows` further down, where the
+    /// body's `own` cells count as room.
+    fn body_fits(&mut self, body: &Body, own: &HashSet<(u16, u16)>, rows: u16) -> bool {
+        body.cells.iter().all(|&(x, y)| {
+            let y = y + rows;
+            y < self.height
+                && (own.contains(&(x, y)) || self.is_empty(y as u32 * self.width as u32 + x as u32))
+        })
+    }
+
+    /// Replaces the grid with `image` scaled to fit, letting gravity take it from there.
+    ///
+    /// Transparent pixels stay empty, as do pixels within `threshold` of the
+    /// image's top left color when a threshold is given.
+    pub fn load_image(&mut self, image: &Image, threshold: Option<u8>) -> bool {
+        self.clear();
+
+        let background = image.pixels[0];
+        let mut placed_pixels = false;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let argb = image.sample(
+                    (x as f32 + 0.5) / self.width as f32,
+                    (y as f32 + 0.5) / self.height as f32,
+                );
+
+                if alpha(argb) < 128
+                    || threshold.is_some_and(|t| channel_distance(argb, background) <= t)
+                {
+                    continue;
+                }
+
+                let index = y as u32 * self.width as u32 + x as u32;
+                self.set_pixel(index, argb & 0xFFFFFF, SAND);
+                placed_pixels = true;
+            }
+        }
+
+        self.highest_row = 0;
+        self.lowest_row = self.height - 1;
+        self.left_skip = 0;
+        self.right_skip = self.width - 1;
+        placed_pixels
+    }
+
     pub fn resize(&mut self, width: u16, height: u16) {
-        resize::smart_resize(
-            &mut self.colors,
-            (self.width as usize, self.height as usize),
-            (width as usize, height as usize),
-        );
+        let old_size = (self.width as usize, self.height as usize);
+        let new_size = (width as usize, height as usize);
+        resize::smart_resize(&mut self.colors, old_size, new_size, EMPTY);
+        resize::smart_resize(&mut self.material_ids, old_size, new_size, SAND);
+        resize::smart_resize(&mut self.velocities, old_size, new_size, 0.0);
+        resize::smart_resize(&mut self.lifetimes, old_size, new_size, 0);
+        resize::smart_resize(&mut self.base_colors, old_size, new_size, EMPTY);
+        resize::smart_resize(&mut self.temperatures, old_size, new_size, AMBIENT);
+        self.next_temperatures
+            .resize(width as usize * height as usize, AMBIENT);
+        resize::smart_resize(&mut self.last_moved, old_size, new_size, 0);
         #[cfg(debug_assertions)]
         self.checked
             .resize(width as usize * height as usize, 0xFFFF0000);
+
+        // Fixtures stay put relative to the bottom left, like the cells.
+        let shift = height as i32 - self.height as i32;
+        self.fixtures = std::mem::take(&mut self.fixtures)
+            .into_iter()
+            .filter_map(|mut fixture| {
+                let (x, y) = fixture.position();
+                let y = y as i32 + shift;
+                if x >= width || !(0..height as i32).contains(&y) {
+                    return None;
+                }
+
+                fixture.set_position((x, y as u16));
+                Some(fixture)
+            })
+            .collect();
+        for body in &mut self.bodies {
+            body.cells = std::mem::take(&mut body.cells)
+                .into_iter()
+                .filter_map(|(x, y)| {
+                    let y = y as i32 + shift;
+                    (x < width && (0..height as i32).contains(&y)).then_some((x, y as u16))
+                })
+                .collect();
+        }
         self.width = width;
         self.height = height;
         self.highest_row = 0;
-        self.lowest_row = self.height - 2;
+        self.lowest_row = self.height - 1;
         self.left_skip = 0;
         self.right_skip = self.width - 1;
     }
 
-    pub(crate) fn set_radius(&mut self, r: f64) {
-        self.radius = r;
+    pub fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    /// Color of the cell at `(x, y)`, `None` if it is empty or outside the grid.
+    pub fn cell(&self, x: u16, y: u16) -> Option<u32> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        let color = self.colors[y as usize * self.width as usize + x as usize];
+        (color != EMPTY).then_some(color)
+    }
+
+    /// Height in cells of the powder and solids resting in `column`. Only cells
+    /// stacked up from the bottom without gaps count, so grains still falling
+    /// onto the pile don't, and neither do liquids or gases lying on top.
+    pub fn surface_height(&self, column: u16) -> u16 {
+        let mut surface = 0;
+        for row in (0..self.height).rev() {
+            let i = row as usize * self.width as usize + column as usize;
+            if self.colors[i] == EMPTY || self.velocities[i] != 0.0 {
+                break;
+            }
+
+            if matches!(
+                self.material(i as u32).movement,
+                Movement::Powder | Movement::Solid
+            ) {
+                surface = self.height - row;
+            }
+        }
+        surface
+    }
+
+    /// Number of cells of every color present.
+    pub fn color_counts(&self) -> HashMap<u32, usize> {
+        let mut counts = HashMap::new();
+        for color in self.colors.iter().filter(|c| **c != EMPTY) {
+            *counts.entry(*color).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Number of cells of every material, indexed by [`MaterialId`].
+    pub fn material_counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.materials.len()];
+        for (color, id) in self.colors.iter().zip(&self.material_ids) {
+            if *color != EMPTY {
+                counts[*id as usize] += 1;
+            }
+        }
+        counts
+    }
+
+    /// Top left and bottom right corner of the occupied cells, inclusive,
+    /// `None` if the grid is empty.
+    pub fn bounding_box(&self) -> Option<((u16, u16), (u16, u16))> {
+        let width = self.width as usize;
+        let mut occupied = self.colors.iter().enumerate().filter(|(_, c)| **c != EMPTY);
+
+        let (first, _) = occupied.next()?;
+        let mut min = ((first % width) as u16, (first / width) as u16);
+        let mut max = min;
+        for (i, _) in occupied {
+            let (x, y) = ((i % width) as u16, (i / width) as u16);
+            min.0 = min.0.min(x);
+            max.0 = max.0.max(x);
+            max.1 = y;
+        }
+        Some((min, max))
+    }
+
+    /// Whether no cell could move or change anymore.
+    pub fn is_settled(&self) -> bool {
+        // Rows and columns are found the way cells move, sides that don't
+        // wrap hold cells in like occupied cells.
+        let below = |y: u16| match y + 1 {
+            next if next < self.height => Some(next),
+            _ => (self.edges.bottom == Edge::Wrap).then_some(0),
+        };
+        let holds = |x: Option<u16>, y: Option<u16>, density: f32| {
+            let (Some(x), Some(y)) = (x, y) else {
+                return true;
+            };
+            self.cell(x, y).is_some() && {
+                let other = self.material(y as u32 * self.width as u32 + x as u32);
+                !other.is_fluid() || other.density >= density
+            }
+        };
+
+        (0..self.height).all(|y| {
+            (0..self.width).all(|x| {
+                if self.cell(x, y).is_none() {
+                    return true;
+                }
+
+                let material = self.material(y as u32 * self.width as u32 + x as u32);
+                if self.is_open_edge(y, material.movement) {
+                    return false;
+                }
+
+                let (left, right) = (self.beside(x, -1), self.beside(x, 1));
+                let solid = |x| holds(x, below(y), material.density);
+                // Grains with a row below them slide or flow off open sides.
+                let open = below(y).is_some() && (self.leaves(x, -1) || self.leaves(x, 1));
+                let supported = !open && solid(Some(x)) && solid(left) && solid(right);
+                match material.movement {
+                    _ if material.is_aging() => false,
+                    Movement::Solid => true,
+                    Movement::Gas { .. } => false,
+                    Movement::Powder => supported,
+                    Movement::Liquid { .. } => {
+                        let filled = |x: Option<u16>| x.is_none_or(|x| self.cell(x, y).is_some());
+                        supported && filled(left) && filled(right)
+                    }
+                }
+            })
+        })
+    }
+
+    /// Estimated angle of repose in degrees, from the average slope of the
+    /// surface over spans of [`REPOSE_SPAN`] columns that aren't flat. `None`
+    /// if the surface is flat.
+    pub fn angle_of_repose(&self) -> Option<f32> {
+        let heights: Vec<_> = (0..self.width).map(|x| self.surface_height(x)).collect();
+        let rises: Vec<_> = heights
+            .windows(REPOSE_SPAN + 1)
+            .map(|span| span[0].abs_diff(span[REPOSE_SPAN]))
+            .filter(|rise| *rise != 0)
+            .collect();
+
+        if rises.is_empty() {
+            return None;
+        }
+
+        let rise = rises.iter().map(|r| *r as f32).sum::<f32>() / rises.len() as f32;
+        Some((rise / REPOSE_SPAN as f32).atan().to_degrees())
+    }
+
+    pub fn stats(&self) -> Stats {
+        let counts = self.color_counts();
+        Stats {
+            grains: counts.values().sum(),
+            colors: counts.len(),
+            materials: self.material_counts(),
+            bounds: self.bounding_box(),
+            settled: self.is_settled(),
+            repose: self.angle_of_repose(),
+        }
+    }
+
+    /// Chance in `-1.0..=1.0` for a falling grain to drift one column, negative is left.
+    pub(crate) fn set_wind(&mut self, wind: f32) {
+        self.wind = wind;
+    }
+
+    pub(crate) fn set_brush(&mut self, brush: Brush) {
+        self.brush = brush;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::{self, GLASS, ICE, WATER};
+
+    const RED: u32 = 0xFF0000;
+    const BLUE: u32 = 0x0000FF;
+
+    fn grid(width: u16, height: u16) -> Grid {
+        let materials = material::defaults();
+        let reactions = Reactions::defaults(&materials);
+        let mut grid = Grid::new(materials, reactions, Physics::default(), Edges::default());
+        grid.resize(width, height);
+        grid
+    }
+
+    fn place(grid: &mut Grid, (x, y): (u16, u16), color: u32, material: MaterialId) {
+        grid.wake(x, y);
+        grid.set_pixel(y as u32 * grid.width as u32 + x as u32, color, material);
+    }
+
+    fn run(grid: &mut Grid, ticks: usize) {
+        for _ in 0..ticks {
+            grid.update();
+        }
+    }
+
+    #[test]
+    fn grains_fall_to_the_floor() {
+        let mut grid = grid(8, 8);
+        place(&mut grid, (3, 0), RED, SAND);
+        assert_eq!(grid.cell(3, 0), Some(RED));
+        assert!(!grid.is_settled());
+
+        run(&mut grid, 20);
+        assert_eq!(grid.cell(3, 0), None);
+        assert_eq!(grid.cell(3, 7), Some(RED));
+        assert!(grid.is_settled());
+    }
+
+    #[test]
+    fn grains_leave_open_sides_only_by_moving_past_them() {
+        let mut grid = grid(8, 8);
+        grid.edges.left = Edge::Open;
+        place(&mut grid, (0, 0), RED, SAND);
+
+        // Falls straight down along the side and rests on the floor.
+        run(&mut grid, 20);
+        assert_eq!(grid.cell(0, 7), Some(RED));
+
+        // Lands on it and slides off the side.
+        place(&mut grid, (0, 0), BLUE, SAND);
+        run(&mut grid, 20);
+        assert_eq!(grid.material_counts()[SAND as usize], 1);
+        assert!(grid.is_settled());
+    }
+
+    #[test]
+    fn grains_spawned_along_an_open_top_fall_in() {
+        let mut grid = grid(8, 8);
+        grid.edges.top = Edge::Open;
+        place(&mut grid, (3, 0), RED, SAND);
+
+        run(&mut grid, 20);
+        assert_eq!(grid.cell(3, 7), Some(RED));
+        assert!(grid.is_settled());
+    }
+
+    #[test]
+    fn grains_can_slide_across_wrapping_sides() {
+        let mut grid = grid(8, 8);
+        grid.edges.left = Edge::Wrap;
+        grid.edges.right = Edge::Wrap;
+        place(&mut grid, (0, 7), RED, SAND);
+        place(&mut grid, (0, 6), BLUE, SAND);
+        assert!(!grid.is_settled());
+
+        grid.edges.left = Edge::Solid;
+        grid.edges.right = Edge::Solid;
+        place(&mut grid, (1, 7), RED, SAND);
+        assert!(grid.is_settled());
+    }
+
+    #[test]
+    fn idle_fixtures_let_the_grid_rest() {
+        let mut grid = grid(8, 8);
+        grid.place(Fixture::Drain { position: (2, 2) });
+        grid.place(Fixture::Source {
+            position: (5, 7),
+            material: SAND,
+            color: RED,
+        });
+        place(&mut grid, (5, 7), BLUE, SAND);
+
+        assert!(!grid.update());
+        assert_eq!(grid.cell(5, 7), Some(BLUE));
+    }
+
+    #[test]
+    fn sticky_grains_keep_rolling_until_they_slide() {
+        let mut grid = grid(8, 8);
+        grid.materials[SAND as usize].stickiness = 0.95;
+        place(&mut grid, (3, 7), RED, SAND);
+        place(&mut grid, (3, 6), BLUE, SAND);
+
+        run(&mut grid, 500);
+        assert_eq!(grid.cell(3, 6), None);
+        assert!(grid.is_settled());
+    }
+
+    #[test]
+    fn heat_spreads_until_it_no_longer_shows() {
+        let mut grid = grid(8, 8);
+        grid.materials[BLOCK as usize].heat = Some(500.0);
+        place(&mut grid, (0, 7), RED, BLOCK);
+
+        run(&mut grid, 50);
+        let temperature = |x: u16, y: u16| grid.temperatures()[y as usize * 8 + x as usize];
+        assert_eq!(temperature(0, 7), 500.0);
+        assert!(temperature(1, 7) > temperature(4, 7));
+        assert!(temperature(4, 7) > temperature(7, 0));
+        assert!(temperature(7, 0) > AMBIENT);
+
+        // The block keeps its heat, the rest settles close to it.
+        let ticks = (0..1000).take_while(|_| grid.update()).count();
+        assert!(ticks < 200);
+    }
+
+    #[test]
+    fn cells_melt_and_freeze_past_their_temperatures() {
+        let mut grid = grid(8, 8);
+        place(&mut grid, (2, 7), RED, SAND);
+        place(&mut grid, (5, 7), BLUE, WATER);
+        grid.temperatures[7 * 8 + 2] = 2000.0;
+        grid.temperatures[7 * 8 + 5] = -200.0;
+        grid.warm = true;
+
+        run(&mut grid, 1);
+        assert_eq!(grid.material_ids[7 * 8 + 2], GLASS);
+        assert_eq!(grid.material_ids[7 * 8 + 5], ICE);
+    }
+
+    /// Drops a three by three body centered on `position`.
+    fn drop_square(grid: &mut Grid, position: (u16, u16)) {
+        grid.set_brush(Brush::Square);
+        assert!(grid.drop_body(position, RED, 2.0));
+    }
+
+    fn square_at(grid: &Grid, (left, top): (u16, u16), material: MaterialId) -> bool {
+        (top..top + 3).all(|y| {
+            (left..left + 3).all(|x| {
+                let i = y as u32 * grid.width as u32 + x as u32;
+                grid.cell(x, y) == Some(RED) && grid.material_ids[i as usize] == material
+            })
+        })
+    }
+
+    #[test]
+    fn bodies_fall_as_one_piece() {
+        let mut grid = grid(16, 16);
+        drop_square(&mut grid, (8, 2));
+        assert!(square_at(&grid, (7, 1), BLOCK));
+
+        run(&mut grid, 1);
+        assert!(square_at(&grid, (7, 2), BLOCK));
+        assert_eq!(grid.material_counts()[BLOCK as usize], 9);
+
+        run(&mut grid, 30);
+        assert!(square_at(&grid, (7, 13), BLOCK));
+        assert!(grid.bodies.is_empty());
+    }
+
+    #[test]
+    fn bodies_land_on_sand() {
+        let mut grid = grid(16, 16);
+        for x in 0..16 {
+            place(&mut grid, (x, 15), BLUE, SAND);
+        }
+        drop_square(&mut grid, (8, 2));
+
+        run(&mut grid, 30);
+        assert!(square_at(&grid, (7, 12), BLOCK));
+        assert_eq!(grid.material_counts()[SAND as usize], 16);
+        assert!(grid.bodies.is_empty());
+    }
+
+    #[test]
+    fn bodies_shatter_on_hard_landings() {
+        let mut grid = grid(16, 32);
+        grid.physics.shatter = 1.0;
+        drop_square(&mut grid, (8, 2));
+
+        run(&mut grid, 60);
+        let counts = grid.material_counts();
+        assert_eq!(counts[BLOCK as usize], 0);
+        assert_eq!(counts[SAND as usize], 9);
+        assert!(grid.bodies.is_empty());
+    }
+
+    #[test]
+    fn cells_outside_the_grid_are_empty() {
+        let mut grid = grid(8, 8);
+        place(&mut grid, (7, 7), RED, SAND);
+        assert_eq!(grid.cell(7, 7), Some(RED));
+        assert_eq!(grid.cell(8, 7), None);
+        assert_eq!(grid.cell(7, 8), None);
+    }
+
+    #[test]
+    fn surface_only_counts_resting_powder() {
+        let mut grid = grid(8, 8);
+        // Sand resting on water, and a grain falling towards it.
+        place(&mut grid, (2, 7), BLUE, WATER);
+        place(&mut grid, (2, 6), RED, SAND);
+        place(&mut grid, (2, 2), RED, SAND);
+        // Water on its own.
+        place(&mut grid, (4, 7), BLUE, WATER);
+
+        assert_eq!(grid.surface_height(2), 2);
+        assert_eq!(grid.surface_height(4), 0);
+        assert_eq!(grid.surface_height(6), 0);
+    }
+
+    #[test]
+    fn bounding_box_covers_every_cell() {
+        let mut grid = grid(8, 8);
+        assert_eq!(grid.bounding_box(), None);
+
+        place(&mut grid, (5, 7), RED, SAND);
+        place(&mut grid, (1, 6), RED, SAND);
+        place(&mut grid, (3, 4), RED, SAND);
+        assert_eq!(grid.bounding_box(), Some(((1, 4), (5, 7))));
+    }
+
+    #[test]
+    fn piles_slope_at_the_angle_of_repose() {
+        let mut grid = grid(48, 32);
+        assert_eq!(grid.angle_of_repose(), None);
+
+        for _ in 0..300 {
+            place(&mut grid, (24, 0), RED, SAND);
+            run(&mut grid, 2);
+        }
+        run(&mut grid, 100);
+
+        assert!(grid.is_settled());
+        let angle = grid.angle_of_repose().unwrap();
+        assert!((30.0..=50.0).contains(&angle), "angle {angle}");
+    }
+
+    #[test]
+    fn stats_count_cells_by_color_and_material() {
+        let mut grid = grid(8, 8);
+        place(&mut grid, (0, 7), RED, SAND);
+        place(&mut grid, (1, 7), RED, SAND);
+        place(&mut grid, (2, 7), BLUE, SAND);
+        place(&mut grid, (3, 7), BLUE, WATER);
+
+        let stats = grid.stats();
+        assert_eq!(stats.grains, 4);
+        assert_eq!(stats.materials[SAND as usize], 3);
+        assert_eq!(stats.materials[WATER as usize], 1);
+        assert_eq!(stats.materials.iter().sum::<usize>(), 4);
+        assert_eq!(grid.color_counts()[&RED], 2);
+        assert_eq!(stats.bounds, Some(((0, 7), (3, 7))));
     }
 }
@@ -0,0 +1,168 @@
+use std::time::{Duration, Instant};
+
+use crate::double::overlay;
+
+const GLYPH_WIDTH: usize = 3;
+const GLYPH_HEIGHT: usize = 5;
+pub const MARGIN: usize = 1;
+pub const SWATCH_SIZE: usize = 5;
+pub const TEXT_COLOR: u32 = 0xFFFFFF;
+pub const BACKGROUND_COLOR: u32 = 0x202020;
+
+/// 3x5 glyphs, one row per byte with the leftmost pixel in bit 2.
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    match c.to_ascii_uppercase() {
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b110, 0b001, 0b010, 0b100, 0b111],
+        '3' => [0b110, 0b001, 0b010, 0b001, 0b110],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b110, 0b001, 0b110],
+        '6' => [0b011, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b110],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '%' => [0b101, 0b001, 0b010, 0b100, 0b101],
+        ' ' => [0; GLYPH_HEIGHT],
+        _ => [0b110, 0b001, 0b010, 0b000, 0b010],
+    }
+}
+
+/// Pixels `text` takes up horizontally, including the gap after it.
+pub fn text_width(text: &str) -> usize {
+    text.chars().count() * (GLYPH_WIDTH + 1)
+}
+
+/// Draws `text` with its top left corner at `(left, top)` through `set`.
+pub fn draw_text(
+    set: &mut impl FnMut(usize, usize, u32),
+    (left, top): (usize, usize),
+    text: &str,
+    color: u32,
+) {
+    for (column, c) in text.chars().enumerate() {
+        let left = left + column * (GLYPH_WIDTH + 1);
+        for (dy, bits) in glyph(c).into_iter().enumerate() {
+            for dx in 0..GLYPH_WIDTH {
+                if bits & (0b100 >> dx) != 0 {
+                    set(left + dx, top + dy, color);
+                }
+            }
+        }
+    }
+}
+
+/// Status text drawn over the sand, toggled with H.
+pub struct Hud {
+    pub enabled: bool,
+    frames: u32,
+    since: Instant,
+    fps: u32,
+}
+
+impl Hud {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            frames: 0,
+            since: Instant::now(),
+            fps: 0,
+        }
+    }
+
+    pub fn flip(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    /// Counts a rendered frame, the FPS is recalculated once per second.
+    pub fn frame(&mut self) {
+        self.frames += 1;
+        let elapsed = self.since.elapsed();
+        if elapsed >= Duration::from_secs(1) {
+            self.fps = (self.frames as f32 / elapsed.as_secs_f32()).round() as u32;
+            self.frames = 0;
+            self.since = Instant::now();
+        }
+    }
+
+    pub fn fps(&self) -> u32 {
+        self.fps
+    }
+
+    /// Draws `lines` in the top left corner with a row of `swatch` colors below.
+    pub fn draw(
+        &self,
+        buffer: &mut [u32],
+        temporaries: &mut Vec<(usize, u32)>,
+        (width, height): (u16, u16),
+        lines: &[String],
+        swatch: &[u32],
+    ) {
+        if !self.enabled {
+            return;
+        }
+
+        let (width, height) = (width as usize, height as usize);
+        let text_width = lines.iter().map(|l| text_width(l)).max().unwrap_or(0);
+        let swatch_width = swatch.len() * (SWATCH_SIZE + 1);
+        let box_width = text_width.max(swatch_width) + MARGIN;
+        let box_height = lines.len() * (GLYPH_HEIGHT + 1) + SWATCH_SIZE + 2 * MARGIN;
+
+        let mut set = |x: usize, y: usize, color: u32| {
+            if x < width && y < height {
+                overlay(buffer, temporaries, y * width + x, color);
+            }
+        };
+
+        for y in 0..box_height {
+            for x in 0..box_width {
+                set(x, y, BACKGROUND_COLOR);
+            }
+        }
+
+        for (row, line) in lines.iter().enumerate() {
+            let top = MARGIN + row * (GLYPH_HEIGHT + 1);
+            draw_text(&mut set, (MARGIN, top), line, TEXT_COLOR);
+        }
+
+        let top = MARGIN + lines.len() * (GLYPH_HEIGHT + 1);
+        for (i, color) in swatch.iter().enumerate() {
+            let left = MARGIN + i * (SWATCH_SIZE + 1);
+            for dy in 0..SWATCH_SIZE {
+                for dx in 0..SWATCH_SIZE {
+                    set(left + dx, top + dy, *color);
+                }
+            }
+        }
+    }
+}
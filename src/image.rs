@@ -0,0 +1,67 @@
+use std::{fs::File, io, io::BufReader, path::Path};
+
+use png::{ColorType, Decoder, Transformations};
+
+/// A decoded RGBA image, one `0xAARRGGBB` value per pixel.
+#[derive(Clone)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u32>,
+}
+
+impl Image {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let mut decoder = Decoder::new(BufReader::new(File::open(path)?));
+        decoder.set_transformations(Transformations::ALPHA | Transformations::STRIP_16);
+
+        let mut reader = decoder.read_info()?;
+        let size = reader
+            .output_buffer_size()
+            .ok_or_else(|| io::Error::other("image too large"))?;
+        let mut buffer = vec![0; size];
+        let info = reader.next_frame(&mut buffer)?;
+        let bytes = &buffer[..info.buffer_size()];
+
+        let pixels = match info.color_type {
+            ColorType::Rgba => bytes
+                .chunks_exact(4)
+                .map(|p| u32::from_be_bytes([p[3], p[0], p[1], p[2]]))
+                .collect(),
+            ColorType::GrayscaleAlpha => bytes
+                .chunks_exact(2)
+                .map(|p| u32::from_be_bytes([p[1], p[0], p[0], p[0]]))
+                .collect(),
+            other => {
+                return Err(io::Error::other(format!(
+                    "unsupported color type {other:?}"
+                )))
+            }
+        };
+
+        Ok(Self {
+            width: info.width as usize,
+            height: info.height as usize,
+            pixels,
+        })
+    }
+
+    /// Nearest neighbour sample at normalized coordinates in `0.0..1.0`.
+    pub fn sample(&self, x: f32, y: f32) -> u32 {
+        let x = ((x * self.width as f32) as usize).min(self.width - 1);
+        let y = ((y * self.height as f32) as usize).min(self.height - 1);
+        self.pixels[y * self.width + x]
+    }
+}
+
+pub fn alpha(argb: u32) -> u8 {
+    (argb >> 24) as u8
+}
+
+/// Largest per channel difference between two colors, ignoring alpha.
+pub fn channel_distance(a: u32, b: u32) -> u8 {
+    (0..3)
+        .map(|shift| ((a >> (shift * 8)) as u8).abs_diff((b >> (shift * 8)) as u8))
+        .max()
+        .unwrap()
+}
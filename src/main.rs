@@ -1,22 +1,82 @@
+use std::sync::Arc;
+
+use brush::Brush;
+use cli::Args;
+use config::Config;
 use double::{update_thread, Event};
 use fastrand::Rng;
-use gradient::{Gradient, Steps};
+use fixture::Fixture;
+use gradient::Steps;
+use hud::Hud;
+use image::Image;
+use material::MaterialId;
 use minifb::{Key, KeyRepeat, MouseButton, Window};
-use radii::RadiusId;
+use palette::{Gradients, Palette};
+use picker::Picker;
+use radii::Radius;
 
-use crate::auto::Auto;
+use crate::auto::{skyline::Profile, Auto};
 
 mod auto;
+mod body;
+mod brush;
+mod cli;
+mod config;
 mod double;
+mod fixture;
 mod gradient;
 mod grid;
+mod hud;
+mod image;
+mod material;
+mod palette;
+mod picker;
 mod radii;
+mod reaction;
 mod resize;
 
 static DEFAULT_WIDTH: usize = 800;
 static DEFAULT_HEIGHT: usize = 600;
+/// Cells around the mouse in which X removes fixtures.
+const FIXTURE_REACH: u16 = 2;
+
+fn title(radius: &Radius) -> String {
+    format!("megalodon - radius {:.1}", radius.get())
+}
 
 fn main() {
+    let args = Args::parse();
+    let config = match &args.config {
+        Some(path) => Config::load(path).unwrap_or_else(|e| {
+            cli::fail(&format!("could not load config {}: {e}", path.display()))
+        }),
+        None => Config::default(),
+    };
+    let palette = args.palette.as_deref().map(|path| {
+        Arc::new(Palette::from_image(path).unwrap_or_else(|e| {
+            cli::fail(&format!("could not load palette {}: {e}", path.display()))
+        }))
+    });
+    let image = args.image.as_deref().map(|path| {
+        Image::load(path)
+            .unwrap_or_else(|e| cli::fail(&format!("could not load image {}: {e}", path.display())))
+    });
+
+    let bitmap = args.brush.as_deref().map(|path| {
+        Arc::new(Image::load(path).unwrap_or_else(|e| {
+            cli::fail(&format!("could not load brush {}: {e}", path.display()))
+        }))
+    });
+
+    let profile = match args.profile.as_deref() {
+        None | Some("terrain") => Profile::Terrain,
+        Some("waves") => Profile::Waves,
+        Some(path) => Profile::from_image(
+            &Image::load(path.as_ref())
+                .unwrap_or_else(|e| cli::fail(&format!("could not load profile {path}: {e}"))),
+        ),
+    };
+
     let mut window = Window::new(
         "megalodon",
         DEFAULT_WIDTH,
@@ -32,33 +92,72 @@ fn main() {
 
     let steps = Steps::new(300).unwrap();
     let mut rng = Rng::new();
-    let mut gradient = Gradient::new(&mut rng, steps);
+    let gradients = Gradients { palette, steps };
+    let mut gradient = gradients.next(&mut rng);
 
+    let materials = config.materials.clone();
+    let reactions = config.reactions.clone();
+    let physics = config.physics;
+    let edges = config.edges;
     let (sender, recv) = std::sync::mpsc::channel();
-    let child = std::thread::spawn(move || update_thread(recv));
+    let child = std::thread::spawn({
+        let materials = materials.clone();
+        move || update_thread(recv, materials, reactions, physics, edges)
+    });
 
     let mut zoom = 3;
-    let mut radius = RadiusId::default();
+    let mut radius = config.radius();
+    let mut wind = 0.0;
+    window.set_title(&title(&radius));
+    let mut brush = Brush::Circle;
+    let mut last_stamp = None;
     let mut last_output_size = (DEFAULT_WIDTH as u16, DEFAULT_HEIGHT as u16);
     let mut pixel_buffer = vec![0u32; DEFAULT_WIDTH * DEFAULT_HEIGHT];
     let mut temporaries = vec![];
-    let mut auto = Auto::new();
+    let mut auto = Auto::new(
+        args.strategy,
+        auto::Settings {
+            playlist: args.playlist,
+            script: args.script.clone(),
+            emitters: args.emitters,
+            gradients: gradients.clone(),
+            colors: config.colors.clone(),
+            profile,
+        },
+    );
+    let mut drop_image = image.is_some();
+    let mut hud = Hud::new();
+    let mut picker = Picker::new();
 
     while window.is_open() && !window.is_key_pressed(Key::Escape, KeyRepeat::No) {
         if window.is_key_pressed(Key::A, KeyRepeat::No) {
             auto.flip();
         }
 
-        auto.tick();
+        if window.is_key_pressed(Key::N, KeyRepeat::No) {
+            auto.next_strategy();
+        }
 
-        if window.is_key_pressed(Key::R, KeyRepeat::No) || auto.choose_new_color(&gradient) {
-            gradient = Gradient::new(&mut rng, steps);
+        if window.is_key_pressed(Key::R, KeyRepeat::No) {
+            gradient = gradients.next(&mut rng);
         }
 
         if window.is_key_pressed(Key::C, KeyRepeat::No) {
             sender.send(Event::Clear).unwrap();
         }
 
+        if window.is_key_pressed(Key::H, KeyRepeat::No) {
+            hud.flip();
+        }
+
+        if window.is_key_pressed(Key::T, KeyRepeat::No) {
+            double::toggle_heat();
+        }
+
+        if window.is_key_pressed(Key::I, KeyRepeat::No) {
+            drop_image = image.is_some();
+        }
+
         if window.is_key_pressed(Key::Up, KeyRepeat::No) {
             zoom += 1;
         }
@@ -67,38 +166,132 @@ fn main() {
             zoom -= 1;
         }
 
+        let old_radius = radius.get();
         if window.is_key_pressed(Key::W, KeyRepeat::No) {
             radius.next_bigger();
-            sender.send(Event::Radius(radius)).unwrap();
         }
 
         if window.is_key_pressed(Key::S, KeyRepeat::No) {
             radius.next_smaller();
-            sender.send(Event::Radius(radius)).unwrap();
         }
 
-        let output_size = window.get_size();
-        let mut mouse_position = window.get_mouse_pos(minifb::MouseMode::Clamp).unwrap();
-        if auto.enabled {
-            let relative = auto.mouse_pos();
-            mouse_position = (
-                (output_size.0 as f32 * relative.0),
-                (output_size.1 as f32 * relative.1),
-            );
+        if let Some((_, scroll)) = window.get_scroll_wheel() {
+            // Horizontal scrolling reports no vertical movement, and
+            // trackpads scroll by fractions of a notch.
+            if scroll != 0.0 {
+                radius.scroll(scroll);
+            }
         }
+
+        if radius.get() != old_radius {
+            window.set_title(&title(&radius));
+        }
+
+        if window.is_key_pressed(Key::B, KeyRepeat::No) {
+            brush = brush.next(bitmap.as_ref());
+            sender.send(Event::Brush(brush.clone())).unwrap();
+        }
+
+        if window.is_key_pressed(Key::P, KeyRepeat::No) {
+            picker.flip();
+        }
+
+        if window.is_key_pressed(Key::M, KeyRepeat::No) {
+            picker.selected = (picker.selected + 1) % materials.len() as MaterialId;
+        }
+
+        let number_keys = [
+            Key::Key1,
+            Key::Key2,
+            Key::Key3,
+            Key::Key4,
+            Key::Key5,
+            Key::Key6,
+            Key::Key7,
+            Key::Key8,
+            Key::Key9,
+            Key::Key0,
+        ];
+        for (id, key) in number_keys.into_iter().enumerate().take(materials.len()) {
+            if window.is_key_pressed(key, KeyRepeat::No) {
+                picker.selected = id as MaterialId;
+            }
+        }
+
+        let output_size = window.get_size();
+        let output_size = ((output_size.0 / zoom) as u16, (output_size.1 / zoom) as u16);
+        let mouse_position = window.get_mouse_pos(minifb::MouseMode::Clamp).unwrap();
         let mouse_position = (
             (mouse_position.0 / zoom as f32).round() as u16,
             (mouse_position.1 / zoom as f32).round() as u16,
         );
 
-        if window.get_mouse_down(MouseButton::Left) || auto.should_spawn() {
-            let color = gradient.next_color();
-            sender.send(Event::Spawn(color, mouse_position)).unwrap();
+        if window.is_key_pressed(Key::E, KeyRepeat::No) {
+            let fixture = Fixture::Source {
+                position: mouse_position,
+                material: picker.selected,
+                color: gradient.next_color(),
+            };
+            sender.send(Event::Place(fixture)).unwrap();
+        }
+
+        if window.is_key_pressed(Key::D, KeyRepeat::No) {
+            let fixture = Fixture::Drain {
+                position: mouse_position,
+            };
+            sender.send(Event::Place(fixture)).unwrap();
+        }
+
+        if window.is_key_pressed(Key::O, KeyRepeat::No) {
+            sender
+                .send(Event::DropBody {
+                    color: gradient.next_color(),
+                    position: mouse_position,
+                    radius: radius.get(),
+                })
+                .unwrap();
+        }
+
+        if window.is_key_pressed(Key::X, KeyRepeat::No) {
+            sender
+                .send(Event::RemoveFixtures(mouse_position, FIXTURE_REACH))
+                .unwrap();
+        }
+
+        let picked = picker.hit(&materials, mouse_position, output_size);
+        if let Some(id) = picked.filter(|_| window.get_mouse_down(MouseButton::Left)) {
+            picker.selected = id;
+            last_stamp = None;
+        } else if window.get_mouse_down(MouseButton::Left) {
+            let from = last_stamp.unwrap_or(mouse_position);
+            for position in brush::stroke(from, mouse_position, radius.get() / 2.0) {
+                let color = gradient.next_color();
+                sender
+                    .send(Event::Spawn {
+                        color,
+                        position,
+                        radius: radius.get(),
+                        material: picker.selected,
+                    })
+                    .unwrap();
+            }
+            last_stamp = Some(mouse_position);
+        } else {
+            last_stamp = None;
+        }
+
+        let surface = double::surface(auto.wants_surface());
+        for event in auto.tick(output_size, radius.get(), &surface) {
+            sender.send(event).unwrap();
+        }
+
+        if auto.wind() != wind {
+            wind = auto.wind();
+            sender.send(Event::Wind(wind)).unwrap();
         }
 
         let mouse_in_window = window.get_mouse_pos(minifb::MouseMode::Discard).is_some();
 
-        let output_size = ((output_size.0 / zoom) as u16, (output_size.1 / zoom) as u16);
         if output_size != last_output_size {
             sender
                 .send(Event::Resize(output_size.0, output_size.1))
@@ -108,23 +301,97 @@ fn main() {
                 &mut pixel_buffer,
                 (last_output_size.0 as usize, last_output_size.1 as usize),
                 (output_size.0 as usize, output_size.1 as usize),
+                grid::EMPTY,
             );
 
             temporaries.clear();
+            last_stamp = None;
         }
         last_output_size = output_size;
 
+        // Sent after any resize so the image is scaled to the current grid.
+        if let Some(image) = image.as_ref().filter(|_| drop_image) {
+            sender
+                .send(Event::Image(image.clone(), args.threshold))
+                .unwrap();
+            drop_image = false;
+        }
+
         double::render_to(
             &mut pixel_buffer,
             &mut temporaries,
-            mouse_in_window,
+            mouse_in_window && picked.is_none(),
             &gradient,
             mouse_position,
             output_size.0,
             output_size.1,
+            &brush,
             radius.get(),
         );
 
+        fixture::draw_markers(
+            &mut pixel_buffer,
+            &mut temporaries,
+            output_size,
+            &double::fixtures(),
+        );
+
+        picker.draw(
+            &mut pixel_buffer,
+            &mut temporaries,
+            output_size,
+            &materials,
+            gradient.peek_color(),
+        );
+
+        hud.frame();
+        if hud.enabled {
+            let auto_status = if auto.enabled {
+                format!("auto: {}", auto.strategy_name())
+            } else {
+                "auto: off".to_owned()
+            };
+            let stats = double::stats().unwrap_or_default();
+            let bounds = stats.bounds.map_or("-".to_owned(), |(min, max)| {
+                format!("{}x{}", max.0 - min.0 + 1, max.1 - min.1 + 1)
+            });
+            let repose = stats
+                .repose
+                .map_or("-".to_owned(), |angle| format!("{angle:.0} deg"));
+            let mut lines = vec![
+                auto_status,
+                format!("brush: {} {:.1}", brush.name(), radius.get()),
+                format!("material: {}", materials[picker.selected as usize].name),
+                format!("zoom: {zoom}"),
+                format!("tick: {:.2}ms", double::tick_time().as_secs_f32() * 1000.0),
+                format!("fps: {}", hud.fps()),
+                format!("grains: {} in {bounds}", stats.grains),
+                format!("colors: {}", stats.colors),
+                format!(
+                    "{} repose: {repose}",
+                    if stats.settled { "settled" } else { "falling" }
+                ),
+            ];
+            lines.extend(
+                stats
+                    .materials
+                    .iter()
+                    .zip(&materials)
+                    .filter(|(count, _)| **count > 0)
+                    .map(|(count, material)| format!("  {}: {count}", material.name)),
+            );
+            hud.draw(
+                &mut pixel_buffer,
+                &mut temporaries,
+                output_size,
+                &lines,
+                &auto
+                    .gradient()
+                    .unwrap_or(&gradient)
+                    .upcoming(8, steps.get() as usize / 8),
+            );
+        }
+
         window
             .update_with_buffer(&pixel_buffer, output_size.0 as _, output_size.1 as _)
             .unwrap();
@@ -0,0 +1,240 @@
+/// Index into the grid's material table.
+pub type MaterialId = u8;
+
+pub const SAND: MaterialId = 0;
+pub const SMOKE: MaterialId = 3;
+pub const WATER: MaterialId = 4;
+#[cfg(test)]
+pub const STONE: MaterialId = 6;
+pub const BLOCK: MaterialId = 10;
+pub const ICE: MaterialId = 11;
+pub const GLASS: MaterialId = 12;
+
+/// What empty cells look like, for materials fading out.
+pub const BACKGROUND: u32 = crate::grid::EMPTY & 0xFFFFFF;
+
+/// Furthest a grain can slide sideways along its row.
+pub const MAX_SLIDE: u16 = 8;
+
+#[derive(Clone, Copy)]
+pub enum Movement {
+    /// Never moves.
+    Solid,
+    /// Falls and piles up.
+    Powder,
+    /// Falls, then spreads up to `flow` cells sideways per tick.
+    Liquid { flow: u16 },
+    /// Rises with `buoyancy` chance per tick, drifting sideways.
+    Gas { buoyancy: f32 },
+}
+
+/// How cells of one material move and react.
+#[derive(Clone, Copy)]
+pub struct Material {
+    pub name: &'static str,
+    pub movement: Movement,
+    /// Colors new cells are picked from, empty to use the brush's color.
+    pub colors: &'static [u32],
+    /// Falling cells sink through liquids and gases lighter than them, water is 1.
+    pub density: f32,
+    /// Chance per tick for a grain to stay put although it could slide
+    /// diagonally, higher gives steeper piles.
+    pub stickiness: f32,
+    /// Chance per cell for a grain with both diagonals blocked to slide
+    /// sideways along its row to a spot it can drop into, up to [`MAX_SLIDE`]
+    /// cells. Higher gives flatter piles.
+    pub slide: f32,
+    /// Range of ticks a cell lives for, forever if `None`.
+    pub lifetime: Option<(u16, u16)>,
+    /// What a cell turns into once its lifetime runs out, nothing if `None`.
+    pub decays_to: Option<MaterialId>,
+    /// Picks a new color from `colors` every tick.
+    pub flicker: bool,
+    /// Color cells blend into over the last ticks of their lifetime, e.g.
+    /// [`BACKGROUND`] to fade out.
+    pub ages_to: Option<u32>,
+    /// Temperature of cells drawn or spawned, the temperature already there
+    /// if `None`.
+    pub temperature: Option<f32>,
+    /// Temperature cells are held at, heating or cooling their surroundings.
+    pub heat: Option<f32>,
+    /// Temperature above which cells turn into another material.
+    pub melts: Option<(f32, MaterialId)>,
+    /// Temperature below which cells turn into another material.
+    pub freezes: Option<(f32, MaterialId)>,
+}
+
+impl Material {
+    /// What every material starts from, a powder that always slides
+    /// diagonally when it can and never further, piling up at 45°.
+    const BASE: Material = Material {
+        name: "sand",
+        movement: Movement::Powder,
+        colors: &[],
+        density: 1.5,
+        stickiness: 0.0,
+        slide: 0.0,
+        lifetime: None,
+        decays_to: None,
+        flicker: false,
+        ages_to: None,
+        temperature: None,
+        heat: None,
+        melts: None,
+        freezes: None,
+    };
+
+    /// Melts into glass once it gets hot enough.
+    pub const SAND: Material = Material {
+        melts: Some((900.0, GLASS)),
+        ..Material::BASE
+    };
+
+    pub const WOOD: Material = Material {
+        name: "wood",
+        movement: Movement::Solid,
+        colors: &[0x8B5A2B, 0x7A4E24, 0x9C6B3A],
+        density: 0.7,
+        ..Material::BASE
+    };
+
+    pub const FIRE: Material = Material {
+        name: "fire",
+        movement: Movement::Gas { buoyancy: 0.4 },
+        colors: &[0xFF4000, 0xFF8000, 0xFFB000, 0xFFE060],
+        density: 0.0,
+        lifetime: Some((30, 90)),
+        decays_to: Some(SMOKE),
+        flicker: true,
+        heat: Some(800.0),
+        ..Material::BASE
+    };
+
+    pub const SMOKE: Material = Material {
+        name: "smoke",
+        movement: Movement::Gas { buoyancy: 0.7 },
+        colors: &[0x505050],
+        density: 0.0,
+        lifetime: Some((120, 360)),
+        ages_to: Some(BACKGROUND),
+        ..Material::BASE
+    };
+
+    pub const WATER: Material = Material {
+        name: "water",
+        movement: Movement::Liquid { flow: 4 },
+        colors: &[0x2060D0, 0x2868D8, 0x1858C8],
+        density: 1.0,
+        // Boils off as steam.
+        melts: Some((100.0, SMOKE)),
+        freezes: Some((0.0, ICE)),
+        ..Material::BASE
+    };
+
+    pub const LAVA: Material = Material {
+        name: "lava",
+        movement: Movement::Liquid { flow: 1 },
+        colors: &[0xE04000, 0xF06010, 0xD03000],
+        density: 2.5,
+        heat: Some(1200.0),
+        ..Material::BASE
+    };
+
+    /// What lava cools down to, heavy and rough enough to pile up steeply.
+    pub const STONE: Material = Material {
+        name: "stone",
+        colors: &[0x707070, 0x808080, 0x686868],
+        density: 2.6,
+        stickiness: 0.6,
+        ..Material::BASE
+    };
+
+    pub const ACID: Material = Material {
+        name: "acid",
+        movement: Movement::Liquid { flow: 3 },
+        colors: &[0x60E020, 0x70F030, 0x50D010],
+        density: 1.2,
+        ..Material::BASE
+    };
+
+    /// Floats on water.
+    pub const OIL: Material = Material {
+        name: "oil",
+        movement: Movement::Liquid { flow: 3 },
+        colors: &[0x3A2A10, 0x45331A, 0x30220A],
+        density: 0.8,
+        ..Material::BASE
+    };
+
+    /// Glows and cools down to grey grains.
+    pub const EMBER: Material = Material {
+        name: "ember",
+        colors: &[0xFF6020, 0xFF8030, 0xE04810],
+        density: 0.9,
+        lifetime: Some((240, 600)),
+        decays_to: Some(SAND),
+        ages_to: Some(0x5A5A5A),
+        heat: Some(500.0),
+        ..Material::BASE
+    };
+
+    /// What bodies are made of, a wall in the brush's color when drawn.
+    pub const BLOCK: Material = Material {
+        name: "block",
+        movement: Movement::Solid,
+        density: 2.0,
+        ..Material::BASE
+    };
+
+    /// Melts back into water once it warms up.
+    pub const ICE: Material = Material {
+        name: "ice",
+        movement: Movement::Solid,
+        colors: &[0xC8E8FF, 0xB8E0F8, 0xD8F0FF],
+        density: 0.9,
+        temperature: Some(-150.0),
+        melts: Some((0.0, WATER)),
+        ..Material::BASE
+    };
+
+    /// What sand melts into.
+    pub const GLASS: Material = Material {
+        name: "glass",
+        movement: Movement::Solid,
+        colors: &[0xA0D0C8, 0xB0E0D8, 0x90C8C0],
+        density: 2.5,
+        ..Material::BASE
+    };
+
+    /// Whether other cells can sink through it.
+    pub fn is_fluid(&self) -> bool {
+        matches!(
+            self.movement,
+            Movement::Liquid { .. } | Movement::Gas { .. }
+        )
+    }
+
+    /// Whether cells change without moving, which keeps them updating.
+    pub fn is_aging(&self) -> bool {
+        self.lifetime.is_some()
+    }
+}
+
+/// The default material table, indexed by [`MaterialId`].
+pub fn defaults() -> Vec<Material> {
+    vec![
+        Material::SAND,
+        Material::WOOD,
+        Material::FIRE,
+        Material::SMOKE,
+        Material::WATER,
+        Material::LAVA,
+        Material::STONE,
+        Material::ACID,
+        Material::OIL,
+        Material::EMBER,
+        Material::BLOCK,
+        Material::ICE,
+        Material::GLASS,
+    ]
+}
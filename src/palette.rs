@@ -0,0 +1,116 @@
+use std::{io, path::Path, sync::Arc};
+
+use fastrand::Rng;
+
+use crate::{
+    gradient::{split_rgb, Gradient, Steps},
+    image::{alpha, Image},
+};
+
+const CLUSTERS: usize = 6;
+const ITERATIONS: usize = 12;
+/// Upper bound on how many pixels are fed into k-means, big photos are subsampled.
+const MAX_SAMPLES: usize = 20_000;
+
+/// Dominant colors of a reference image, most common first.
+pub struct Palette {
+    colors: Vec<u32>,
+}
+
+fn distance(a: (f32, f32, f32), b: (f32, f32, f32)) -> f32 {
+    (a.0 - b.0).powi(2) + (a.1 - b.1).powi(2) + (a.2 - b.2).powi(2)
+}
+
+fn nearest(centers: &[(f32, f32, f32)], color: (f32, f32, f32)) -> usize {
+    (0..centers.len())
+        .min_by(|a, b| distance(centers[*a], color).total_cmp(&distance(centers[*b], color)))
+        .unwrap()
+}
+
+impl Palette {
+    pub fn from_image(path: &Path) -> io::Result<Self> {
+        let image = Image::load(path)?;
+        // Sample on a regular grid so both axes are covered evenly.
+        let stride = ((image.pixels.len() / MAX_SAMPLES) as f32).sqrt().max(1.0) as usize;
+
+        let samples: Vec<(f32, f32, f32)> = (0..image.height)
+            .step_by(stride)
+            .flat_map(|y| (0..image.width).step_by(stride).map(move |x| (x, y)))
+            .map(|(x, y)| image.pixels[y * image.width + x])
+            .filter(|argb| alpha(*argb) >= 128)
+            .map(|argb| {
+                let (r, g, b) = split_rgb(argb);
+                (r as f32, g as f32, b as f32)
+            })
+            .collect();
+
+        if samples.is_empty() {
+            return Err(io::Error::other("image has no opaque pixels"));
+        }
+
+        // Seed the centers with samples spread evenly across the image.
+        let k = CLUSTERS.min(samples.len());
+        let mut centers: Vec<_> = (0..k).map(|i| samples[i * samples.len() / k]).collect();
+        let mut counts = vec![0usize; k];
+
+        for _ in 0..ITERATIONS {
+            let mut sums = vec![(0.0, 0.0, 0.0); k];
+            counts.fill(0);
+
+            for sample in &samples {
+                let i = nearest(&centers, *sample);
+                sums[i].0 += sample.0;
+                sums[i].1 += sample.1;
+                sums[i].2 += sample.2;
+                counts[i] += 1;
+            }
+
+            for i in 0..k {
+                if counts[i] != 0 {
+                    let n = counts[i] as f32;
+                    centers[i] = (sums[i].0 / n, sums[i].1 / n, sums[i].2 / n);
+                }
+            }
+        }
+
+        let mut clusters: Vec<_> = centers.into_iter().zip(counts).collect();
+        clusters.retain(|(_, count)| *count != 0);
+        clusters.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+
+        let colors = clusters
+            .into_iter()
+            .map(|((r, g, b), _)| ((r as u32) << 16) | ((g as u32) << 8) | b as u32)
+            .collect();
+
+        Ok(Self { colors })
+    }
+
+    /// Gradient between two different dominant colors.
+    pub fn gradient(&self, rng: &mut Rng, steps: Steps) -> Gradient {
+        let start = rng.usize(..self.colors.len());
+        let mut end = rng.usize(..self.colors.len());
+        if self.colors.len() > 1 {
+            while end == start {
+                end = rng.usize(..self.colors.len());
+            }
+        }
+
+        Gradient::between(self.colors[start], self.colors[end], steps)
+    }
+}
+
+/// Where new gradients come from, a palette if one was loaded or random colors otherwise.
+#[derive(Clone)]
+pub struct Gradients {
+    pub palette: Option<Arc<Palette>>,
+    pub steps: Steps,
+}
+
+impl Gradients {
+    pub fn next(&self, rng: &mut Rng) -> Gradient {
+        match &self.palette {
+            Some(palette) => palette.gradient(rng, self.steps),
+            None => Gradient::new(rng, self.steps),
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use crate::{
+    double::overlay,
+    hud::{draw_text, text_width, BACKGROUND_COLOR, MARGIN, SWATCH_SIZE, TEXT_COLOR},
+    material::{Material, MaterialId},
+};
+
+const HIGHLIGHT_COLOR: u32 = 0x505050;
+// Swatches and text are the same height.
+const STRIP_HEIGHT: usize = SWATCH_SIZE + 2 * MARGIN;
+
+/// Strip of material swatches along the bottom edge, toggled with P. Click
+/// an entry to draw with that material, keys 1 to 9 and 0 pick the first
+/// ten and M steps through all of them.
+pub struct Picker {
+    pub enabled: bool,
+    pub selected: MaterialId,
+}
+
+impl Picker {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            selected: 0,
+        }
+    }
+
+    pub fn flip(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    /// Left edge, top edge and width of every entry in material order,
+    /// wrapping onto more rows when they don't fit next to each other.
+    fn entries(materials: &[Material], (width, height): (u16, u16)) -> Vec<(usize, usize, usize)> {
+        let mut rows = vec![vec![]];
+        let mut left = 0;
+        for material in materials {
+            let entry_width = MARGIN + SWATCH_SIZE + 1 + text_width(material.name) + MARGIN;
+            if left > 0 && left + entry_width > width as usize {
+                rows.push(vec![]);
+                left = 0;
+            }
+            rows.last_mut().unwrap().push((left, entry_width));
+            left += entry_width;
+        }
+
+        let top = (height as usize).saturating_sub(rows.len() * STRIP_HEIGHT);
+        rows.into_iter()
+            .enumerate()
+            .flat_map(|(row, entries)| {
+                entries
+                    .into_iter()
+                    .map(move |(left, width)| (left, top + row * STRIP_HEIGHT, width))
+            })
+            .collect()
+    }
+
+    /// The material under `position`, if the strip is shown there.
+    pub fn hit(
+        &self,
+        materials: &[Material],
+        (x, y): (u16, u16),
+        size: (u16, u16),
+    ) -> Option<MaterialId> {
+        if !self.enabled {
+            return None;
+        }
+
+        let (x, y) = (x as usize, y as usize);
+        Self::entries(materials, size)
+            .into_iter()
+            .position(|(left, top, width)| {
+                (left..left + width).contains(&x) && (top..top + STRIP_HEIGHT).contains(&y)
+            })
+            .map(|i| i as MaterialId)
+    }
+
+    /// Draws the strip, materials without colors of their own are shown in `brush_color`.
+    pub fn draw(
+        &self,
+        buffer: &mut [u32],
+        temporaries: &mut Vec<(usize, u32)>,
+        (width, height): (u16, u16),
+        materials: &[Material],
+        brush_color: u32,
+    ) {
+        if !self.enabled {
+            return;
+        }
+
+        let entries = Self::entries(materials, (width, height));
+        let (width, height) = (width as usize, height as usize);
+        let mut set = |x: usize, y: usize, color: u32| {
+            if x < width && y < height {
+                overlay(buffer, temporaries, y * width + x, color);
+            }
+        };
+
+        for (id, (left, top, entry_width)) in entries.into_iter().enumerate() {
+            let background = if id == self.selected as usize {
+                HIGHLIGHT_COLOR
+            } else {
+                BACKGROUND_COLOR
+            };
+            for y in top..top + STRIP_HEIGHT {
+                for x in left..left + entry_width {
+                    set(x, y, background);
+                }
+            }
+
+            let material = &materials[id];
+            let swatch = material.colors.first().copied().unwrap_or(brush_color);
+            for dy in 0..SWATCH_SIZE {
+                for dx in 0..SWATCH_SIZE {
+                    set(left + MARGIN + dx, top + MARGIN + dy, swatch);
+                }
+            }
+
+            let text_left = left + MARGIN + SWATCH_SIZE + 1;
+            draw_text(
+                &mut set,
+                (text_left, top + MARGIN),
+                material.name,
+                TEXT_COLOR,
+            );
+        }
+    }
+}
@@ -1,24 +1,50 @@
-static RADII: &[f64] = &[1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0, 128.0, 256.0];
+pub static RADII: &[f64] = &[1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0, 128.0, 256.0];
 
-#[derive(Clone, Copy)]
-pub struct RadiusId(u8);
+/// Factor applied to the radius per mouse wheel notch.
+const SCROLL_FACTOR: f64 = 1.1;
 
-impl Default for RadiusId {
+#[derive(Clone)]
+pub struct Radius {
+    value: f64,
+    min: f64,
+    max: f64,
+    /// Sizes W and S jump between, sorted ascending.
+    table: Vec<f64>,
+}
+
+impl Default for Radius {
     fn default() -> Self {
-        RadiusId(3)
+        Radius::new(RADII[3], RADII[0], RADII[RADII.len() - 1], RADII.to_vec())
     }
 }
 
-impl RadiusId {
+impl Radius {
+    pub fn new(value: f64, min: f64, max: f64, mut table: Vec<f64>) -> Self {
+        table.sort_by(f64::total_cmp);
+        Self {
+            value: value.clamp(min, max),
+            min,
+            max,
+            table,
+        }
+    }
+
     pub fn next_bigger(&mut self) {
-        self.0 = (self.0 + 1).min(RADII.len() as u8 - 1)
+        let next = self.table.iter().find(|r| **r > self.value);
+        self.value = next.copied().unwrap_or(self.max).min(self.max);
     }
 
     pub fn next_smaller(&mut self) {
-        self.0 = self.0.saturating_sub(1);
+        let next = self.table.iter().rev().find(|r| **r < self.value);
+        self.value = next.copied().unwrap_or(self.min).max(self.min);
+    }
+
+    /// Grows or shrinks the radius by `notches` of the mouse wheel.
+    pub fn scroll(&mut self, notches: f32) {
+        self.value = (self.value * SCROLL_FACTOR.powf(notches as f64)).clamp(self.min, self.max);
     }
 
     pub fn get(&self) -> f64 {
-        RADII[self.0 as usize]
+        self.value
     }
 }
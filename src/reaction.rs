@@ -0,0 +1,184 @@
+use std::{fs, io, path::Path};
+
+use crate::material::{Material, MaterialId};
+
+/// The reactions used without a `reactions` key in the config.
+const DEFAULT: &str = include_str!("../assets/reactions.txt");
+
+/// What a cell turns into, and what its neighbour turns into, when touching
+/// a cell of material `with`. `None` leaves the cell empty.
+#[derive(Clone, Copy)]
+pub struct Reaction {
+    pub with: MaterialId,
+    pub into: Option<MaterialId>,
+    pub other_into: Option<MaterialId>,
+    /// Chance per tick, rolled by whichever cell comes first in the grid.
+    pub chance: f32,
+}
+
+/// Reactions of every material, indexed by [`MaterialId`].
+#[derive(Clone)]
+pub struct Reactions(Vec<Vec<Reaction>>);
+
+impl Reactions {
+    pub fn defaults(materials: &[Material]) -> Self {
+        Self::parse(DEFAULT, materials).expect("default reactions are valid")
+    }
+
+    pub fn load(path: &Path, materials: &[Material]) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?, materials).map_err(io::Error::other)
+    }
+
+    /// Parses one `a + b -> c + d chance` rule per line, `#` starts a comment.
+    fn parse(text: &str, materials: &[Material]) -> Result<Self, String> {
+        let mut reactions = vec![vec![]; materials.len()];
+        for (number, line) in text.lines().enumerate() {
+            let words: Vec<_> = line.split('#').next().unwrap().split_whitespace().collect();
+            if words.is_empty() {
+                continue;
+            }
+
+            let (a, reaction) =
+                parse_rule(&words, materials).map_err(|e| format!("line {}: {e}", number + 1))?;
+
+            // Either cell may notice the other first, which both cells of
+            // the same material already can.
+            reactions[a as usize].push(reaction);
+            if reaction.with == a {
+                continue;
+            }
+            reactions[reaction.with as usize].push(Reaction {
+                with: a,
+                into: reaction.other_into,
+                other_into: reaction.into,
+                chance: reaction.chance,
+            });
+        }
+        Ok(Self(reactions))
+    }
+
+    pub fn of(&self, material: MaterialId) -> &[Reaction] {
+        &self.0[material as usize]
+    }
+}
+
+fn find(name: &str, materials: &[Material]) -> Result<MaterialId, String> {
+    materials
+        .iter()
+        .position(|m| m.name == name)
+        .map(|id| id as MaterialId)
+        .ok_or_else(|| format!("unknown material {name:?}"))
+}
+
+/// A material a cell turns into, `empty` for none.
+fn find_result(name: &str, materials: &[Material]) -> Result<Option<MaterialId>, String> {
+    match name {
+        "empty" => Ok(None),
+        _ => find(name, materials).map(Some),
+    }
+}
+
+/// Parses `a + b -> c + d chance` into `a` and its reaction.
+fn parse_rule(words: &[&str], materials: &[Material]) -> Result<(MaterialId, Reaction), String> {
+    let [a, "+", b, "->", c, "+", d, chance] = *words else {
+        return Err("expected `a + b -> c + d chance`".to_owned());
+    };
+
+    let chance = match chance.parse() {
+        Ok(chance) if (0.0..=1.0).contains(&chance) => chance,
+        _ => return Err(format!("expected a chance between 0 and 1, got {chance:?}")),
+    };
+
+    let reaction = Reaction {
+        with: find(b, materials)?,
+        into: find_result(c, materials)?,
+        other_into: find_result(d, materials)?,
+        chance,
+    };
+    Ok((find(a, materials)?, reaction))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::{self, SAND, STONE, WATER};
+
+    #[test]
+    fn default_reactions_parse() {
+        Reactions::defaults(&material::defaults());
+    }
+
+    #[test]
+    fn rules_apply_to_both_materials() {
+        let text = "# comment\n\nsand + water -> empty + stone 0.5 # trailing\n";
+        let reactions = Reactions::parse(text, &material::defaults()).unwrap();
+
+        let [reaction] = reactions.of(SAND) else {
+            panic!("expected one reaction for sand");
+        };
+        assert_eq!(reaction.with, WATER);
+        assert_eq!(reaction.into, None);
+        assert_eq!(reaction.other_into, Some(STONE));
+        assert_eq!(reaction.chance, 0.5);
+
+        let [reaction] = reactions.of(WATER) else {
+            panic!("expected one reaction for water");
+        };
+        assert_eq!(reaction.with, SAND);
+        assert_eq!(reaction.into, Some(STONE));
+        assert_eq!(reaction.other_into, None);
+        assert_eq!(reaction.chance, 0.5);
+    }
+
+    #[test]
+    fn rules_within_one_material_are_kept_once() {
+        let text = "water + water -> water + stone 0.5";
+        let reactions = Reactions::parse(text, &material::defaults()).unwrap();
+        assert_eq!(reactions.of(WATER).len(), 1);
+    }
+
+    #[test]
+    fn invalid_rules_are_reported_with_their_line() {
+        let cases = [
+            ("sand + water", "line 1: expected `a + b -> c + d chance`"),
+            (
+                "sand + water -> sand + stone",
+                "line 1: expected `a + b -> c + d chance`",
+            ),
+            (
+                "sand water -> sand stone 0.5",
+                "line 1: expected `a + b -> c + d chance`",
+            ),
+            (
+                "sand + water -> sand + stone often",
+                "line 1: expected a chance between 0 and 1, got \"often\"",
+            ),
+            (
+                "sand + water -> sand + stone 1.5",
+                "line 1: expected a chance between 0 and 1, got \"1.5\"",
+            ),
+            (
+                "mud + water -> sand + stone 0.5",
+                "line 1: unknown material \"mud\"",
+            ),
+            (
+                "sand + mud -> sand + stone 0.5",
+                "line 1: unknown material \"mud\"",
+            ),
+            (
+                "sand + water -> mud + stone 0.5",
+                "line 1: unknown material \"mud\"",
+            ),
+            (
+                "# comment\n\nsand + water -> sand + mud 0.5",
+                "line 3: unknown material \"mud\"",
+            ),
+        ];
+
+        let materials = material::defaults();
+        for (text, error) in cases {
+            let result = Reactions::parse(text, &materials).err();
+            assert_eq!(result.as_deref(), Some(error), "{text:?}");
+        }
+    }
+}
@@ -1,9 +1,10 @@
-use crate::grid::EMPTY;
-
-pub fn smart_resize(
-    buffer: &mut Vec<u32>,
+/// Resizes a row-major `buffer` in place, keeping the bottom left anchored
+/// and filling new space with `empty`.
+pub fn smart_resize<T: Copy>(
+    buffer: &mut Vec<T>,
     (old_width, old_height): (usize, usize),
     (new_width, new_height): (usize, usize),
+    empty: T,
 ) {
     let old_length = old_width * old_height;
     let new_length = new_width * new_height;
@@ -25,13 +26,13 @@ pub fn smart_resize(
     } else if new_width > old_width {
         // Add empty space to the right side
         let new_space = (new_width - old_width) * old_height;
-        buffer.resize(old_length + new_space, EMPTY);
+        buffer.resize(old_length + new_space, empty);
 
         for row in (0..old_height).rev() {
             let new_start = row * new_width;
             let row_offset = row * old_width;
             buffer.copy_within(row_offset..row_offset + old_width, new_start);
-            buffer[new_start + old_width..new_start + new_width].fill(EMPTY);
+            buffer[new_start + old_width..new_start + new_width].fill(empty);
         }
     }
 
@@ -44,9 +45,9 @@ pub fn smart_resize(
         let height_diff = new_height - old_height;
         let new_space = new_width * height_diff;
 
-        buffer.resize(new_length, 0x00FF0000);
+        buffer.resize(new_length, empty);
         buffer.copy_within(..new_width * old_height, new_space);
-        buffer[..new_space].fill(EMPTY);
+        buffer[..new_space].fill(empty);
     }
 
     assert_eq!(buffer.len(), new_length);